@@ -1,10 +1,20 @@
-use criterion::{criterion_group, criterion_main, Criterion};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
 use itertools::Itertools;
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
-use rand::{thread_rng, Rng};
+use rand::{thread_rng, Rng, SeedableRng};
 
-use table_problem::generator::generate_problem;
-use table_problem::objective_value_calculator::v1::ObjectiveValueCalculator;
+use table_problem::algorithm;
+use table_problem::binary::{problem_from_msgpack, problem_to_msgpack};
+use table_problem::generator::profile::GeneratorProfile;
+use table_problem::generator::{generate_problem, generate_problem_with_rng};
+use table_problem::objective_value_calculator::v1::{self, ObjectiveValueCalculator};
+use table_problem::objective_value_calculator::{v2, v3, v4, v5, v6, v7};
+use table_problem::problem::{AlgorithmResults, ProblemDescription, MAX_PEOPLE_FOR_TABLE};
+use table_problem::schema::{problem_from_json, problem_to_json};
 
 fn objective_function_benchmark(c: &mut Criterion) {
     let no_of_people = 1200;
@@ -61,5 +71,309 @@ fn objective_function_benchmark(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, objective_function_benchmark);
+fn dense_calculator_benchmark(c: &mut Criterion) {
+    let no_of_people = 1200;
+    let no_of_tables = 220;
+    let problem = generate_problem(no_of_people, no_of_tables);
+    let calculator_v6 = v6::ObjectiveValueCalculator::new(&problem);
+    let calculator_v7 = v7::ObjectiveValueCalculator::new(&problem);
+
+    let all_people_ids = (0..no_of_people).collect_vec();
+
+    let mut group = c.benchmark_group("dense calculator");
+    for no_of_people_at_table in [1, 2, 4, 6] {
+        group.bench_with_input(
+            BenchmarkId::new("v6", no_of_people_at_table),
+            &no_of_people_at_table,
+            |b, &no_of_people_at_table| {
+                b.iter(|| {
+                    let table_day_id = thread_rng().gen_range(0..no_of_tables);
+                    let people = all_people_ids
+                        .choose_multiple(&mut thread_rng(), no_of_people_at_table)
+                        .cloned()
+                        .collect_vec();
+                    calculator_v6.table_value(table_day_id, &people);
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("v7", no_of_people_at_table),
+            &no_of_people_at_table,
+            |b, &no_of_people_at_table| {
+                b.iter(|| {
+                    let table_day_id = thread_rng().gen_range(0..no_of_tables);
+                    let people = all_people_ids
+                        .choose_multiple(&mut thread_rng(), no_of_people_at_table)
+                        .cloned()
+                        .collect_vec();
+                    calculator_v7.table_value(table_day_id, &people);
+                })
+            },
+        );
+        group.bench_with_input(
+            // Generated people and table day ids are the same as their indexes
+            BenchmarkId::new("v7 by index", no_of_people_at_table),
+            &no_of_people_at_table,
+            |b, &no_of_people_at_table| {
+                b.iter(|| {
+                    let table_day_index = thread_rng().gen_range(0..no_of_tables);
+                    let people_indexes = all_people_ids
+                        .choose_multiple(&mut thread_rng(), no_of_people_at_table)
+                        .cloned()
+                        .collect_vec();
+                    calculator_v7.table_value_by_index(table_day_index, &people_indexes);
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+/// Benchmarks table_value of one calculator version on the given samples
+macro_rules! bench_calculator_version {
+    ($group:expr, $version:ident, $problem:expr, $samples:expr, $no_of_people_at_table:expr) => {{
+        let calculator = $version::ObjectiveValueCalculator::new($problem);
+        $group.bench_with_input(
+            BenchmarkId::new(stringify!($version), $no_of_people_at_table),
+            $samples,
+            |b, samples| {
+                b.iter(|| {
+                    samples
+                        .iter()
+                        .map(|(table_day_id, people)| calculator.table_value(*table_day_id, people))
+                        .sum::<f64>()
+                })
+            },
+        );
+    }};
+}
+
+const CALCULATOR_VERSIONS: [&str; 7] = ["v1", "v2", "v3", "v4", "v5", "v6", "v7"];
+const CALCULATOR_TABLE_SIZES: [usize; 4] = [1, 2, 4, 6];
+
+/// Every calculator version scores the same seeded tables, throughput is table values per second
+fn calculator_versions_benchmark(c: &mut Criterion) {
+    let no_of_people = 1200;
+    let no_of_tables = 220;
+    let mut rng = StdRng::seed_from_u64(0);
+    let problem = generate_problem_with_rng(no_of_people, no_of_tables, &mut rng);
+    let all_people_ids = (0..no_of_people).collect_vec();
+
+    let mut group = c.benchmark_group("calculator");
+    for no_of_people_at_table in CALCULATOR_TABLE_SIZES {
+        let samples = (0..1000)
+            .map(|_| {
+                (
+                    rng.gen_range(0..no_of_tables),
+                    all_people_ids
+                        .choose_multiple(&mut rng, no_of_people_at_table)
+                        .cloned()
+                        .collect_vec(),
+                )
+            })
+            .collect_vec();
+        group.throughput(Throughput::Elements(samples.len() as u64));
+        bench_calculator_version!(group, v1, &problem, &samples, no_of_people_at_table);
+        bench_calculator_version!(group, v2, &problem, &samples, no_of_people_at_table);
+        bench_calculator_version!(group, v3, &problem, &samples, no_of_people_at_table);
+        bench_calculator_version!(group, v4, &problem, &samples, no_of_people_at_table);
+        bench_calculator_version!(group, v5, &problem, &samples, no_of_people_at_table);
+        bench_calculator_version!(group, v6, &problem, &samples, no_of_people_at_table);
+        bench_calculator_version!(group, v7, &problem, &samples, no_of_people_at_table);
+    }
+    group.finish();
+
+    print_calculator_comparison();
+}
+
+/// Markdown table of the mean time per 1000 table values, read back from the Criterion estimates
+fn print_calculator_comparison() {
+    let criterion_dir = std::env::var("CARGO_TARGET_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("target"))
+        .join("criterion")
+        .join("calculator");
+
+    println!();
+    println!(
+        "| people at table | {} |",
+        CALCULATOR_VERSIONS.iter().join(" | ")
+    );
+    println!("|---|{}", "---:|".repeat(CALCULATOR_VERSIONS.len()));
+    for no_of_people_at_table in CALCULATOR_TABLE_SIZES {
+        let mean_times = CALCULATOR_VERSIONS.iter().map(|version| {
+            std::fs::read_to_string(
+                criterion_dir
+                    .join(version)
+                    .join(no_of_people_at_table.to_string())
+                    .join("new")
+                    .join("estimates.json"),
+            )
+            .ok()
+            .and_then(|estimates| serde_json::from_str::<serde_json::Value>(&estimates).ok())
+            .and_then(|estimates| estimates["mean"]["point_estimate"].as_f64())
+            // Never measured, e.g. always filtered out
+            .map_or("-".to_string(), |nanoseconds| {
+                format!("{:.1} µs", nanoseconds / 1000.0)
+            })
+        });
+        println!(
+            "| {} | {} |",
+            no_of_people_at_table,
+            mean_times.into_iter().join(" | ")
+        );
+    }
+    println!();
+}
+
+fn batch_insertion_benchmark(c: &mut Criterion) {
+    let no_of_people = 1200;
+    let no_of_tables = 220;
+    let problem = generate_problem(no_of_people, no_of_tables);
+    let calculator_v6 = v6::ObjectiveValueCalculator::new(&problem);
+    let calculator_v7 = v7::ObjectiveValueCalculator::new(&problem);
+
+    // Every table day gets a random number of people, the same for both calculators
+    let mut all_people_ids = (0..no_of_people).collect_vec();
+    all_people_ids.shuffle(&mut thread_rng());
+    let mut remaining_people = all_people_ids.into_iter();
+    let tables = (0..no_of_tables)
+        .map(|_| {
+            remaining_people
+                .by_ref()
+                .take(thread_rng().gen_range(0..MAX_PEOPLE_FOR_TABLE))
+                .collect_vec()
+        })
+        .collect_vec();
+    let mut seating = v7::TableDaysSeating::new(&calculator_v7);
+    for (table_day_index, people) in tables.iter().enumerate() {
+        for (seat, person_index) in people.iter().enumerate() {
+            seating.insert(&calculator_v7, table_day_index, seat, *person_index);
+        }
+    }
+
+    let mut group = c.benchmark_group("batch insertion");
+    group.bench_function("v6 per table day", |b| {
+        b.iter(|| {
+            let person_id = thread_rng().gen_range(0..no_of_people);
+            let mut best_insertion_value = f64::MIN;
+            for (table_day_id, people) in tables.iter().enumerate() {
+                if !calculator_v6.is_available(person_id, table_day_id) {
+                    continue;
+                }
+                for insertion_index in 0..people.len().max(1) {
+                    best_insertion_value = best_insertion_value.max(calculator_v6.insertion_delta(
+                        table_day_id,
+                        people,
+                        insertion_index,
+                        person_id,
+                    ));
+                }
+            }
+            best_insertion_value
+        })
+    });
+    let mut values = vec![];
+    let mut positions = vec![];
+    group.bench_function("v7 all table days", |b| {
+        b.iter(|| {
+            let person_index = thread_rng().gen_range(0..no_of_people);
+            calculator_v7.insertion_values(&seating, person_index, &mut values, &mut positions);
+            values.iter().copied().fold(f64::MIN, f64::max)
+        })
+    });
+    group.finish();
+}
+
+/// Loading the same problem from JSON and MessagePack, throughput is bytes of the file per second
+fn file_format_benchmark(c: &mut Criterion) {
+    let problem = generate_problem_with_rng(1200, 220, &mut StdRng::seed_from_u64(0));
+    let json = problem_to_json(&problem).unwrap();
+    let binary = problem_to_msgpack(&problem).unwrap();
+
+    let mut group = c.benchmark_group("file format");
+    group.throughput(Throughput::Bytes(json.len() as u64));
+    group.bench_function("load json", |b| {
+        b.iter(|| problem_from_json(&json).unwrap())
+    });
+    group.throughput(Throughput::Bytes(binary.len() as u64));
+    group.bench_function("load msgpack", |b| {
+        b.iter(|| problem_from_msgpack(&binary).unwrap())
+    });
+    group.finish();
+    println!(
+        "file format: json {} bytes, msgpack {} bytes",
+        json.len(),
+        binary.len()
+    );
+}
+
+type Solver = fn(&ProblemDescription, chrono::Duration) -> AlgorithmResults;
+
+/// Time of a single solver iteration, so the reported throughput is iterations per second.
+/// The final objective is not something Criterion can track, so its mean is printed after each benchmark.
+fn algorithm_benchmark(c: &mut Criterion) {
+    let solvers: [(&str, Solver); 7] = [
+        ("v1", algorithm::v1::solve),
+        ("v2", algorithm::v2::solve),
+        ("v3", algorithm::v3::solve),
+        ("v4", algorithm::v4::solve),
+        ("v5", algorithm::v5::solve),
+        ("v6", algorithm::v6::solve),
+        ("v7", algorithm::v7::solve),
+    ];
+    let time_limit = chrono::Duration::milliseconds(100);
+
+    let mut group = c.benchmark_group("algorithm");
+    group
+        .sample_size(10)
+        .measurement_time(Duration::from_secs(5))
+        .throughput(Throughput::Elements(1));
+    for no_of_people in [60, 150, 300] {
+        let problem = generate_problem_with_rng(
+            no_of_people,
+            GeneratorProfile::default().no_of_table_days(no_of_people),
+            &mut StdRng::seed_from_u64(no_of_people as u64),
+        );
+        let calculator = v7::ObjectiveValueCalculator::new(&problem);
+
+        for (name, solve) in solvers {
+            let mut objective_values = vec![];
+            group.bench_with_input(
+                BenchmarkId::new(name, no_of_people),
+                &problem,
+                |b, problem| {
+                    b.iter_custom(|iters| {
+                        let mut total = Duration::ZERO;
+                        for _ in 0..iters {
+                            let results = solve(problem, time_limit);
+                            total += results.elapsed.to_std().unwrap()
+                                / results.no_of_iterations.max(1) as u32;
+                            objective_values.push(calculator.solution_value(&results.solution));
+                        }
+                        total
+                    })
+                },
+            );
+            println!(
+                "algorithm/{}/{} mean final objective {:.3} over {} runs",
+                name,
+                no_of_people,
+                objective_values.iter().sum::<f64>() / objective_values.len().max(1) as f64,
+                objective_values.len()
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    objective_function_benchmark,
+    dense_calculator_benchmark,
+    calculator_versions_benchmark,
+    batch_insertion_benchmark,
+    file_format_benchmark,
+    algorithm_benchmark
+);
 criterion_main!(benches);
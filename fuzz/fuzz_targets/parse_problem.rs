@@ -0,0 +1,21 @@
+//! Arbitrary bytes as a problem file: parsing, migration and validation must never panic
+//!
+//! `cargo +nightly fuzz run parse_problem`
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use table_problem::schema::problem_from_json;
+use table_problem::validator::validate_problem;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(json) = std::str::from_utf8(data) else {
+        return;
+    };
+    if let Ok(problem) = problem_from_json(json) {
+        let _ = validate_problem(&problem);
+        let _ = problem.pair_relations();
+        let _ = problem.seatings_by_availability().count();
+    }
+});
@@ -0,0 +1,56 @@
+//! Arbitrary bytes as a problem file: every solver must solve every small problem that passes
+//! validation into a valid solution. Validation does not make a problem feasible, so people
+//! who must be seated may be left without a seat, but only if no seat is left for them.
+//!
+//! `cargo +nightly fuzz run solve_problem`
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use table_problem::experiment::{solver_by_name, SOLVER_NAMES};
+use table_problem::schema::problem_from_json;
+use table_problem::validator::{check_solution, validate_problem};
+
+/// Bigger problems only make every run slower without reaching new code
+const MAX_PEOPLE: usize = 50;
+const MAX_TABLE_DAYS: usize = 20;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(Ok(mut problem)) = std::str::from_utf8(data).map(problem_from_json) else {
+        return;
+    };
+    if problem.people.len() > MAX_PEOPLE
+        || problem.tables.len() > MAX_TABLE_DAYS
+        || validate_problem(&problem).is_err()
+    {
+        return;
+    }
+    let unassigned_penalties = problem
+        .people
+        .iter()
+        .map(|p| p.unassigned_penalty)
+        .collect::<Vec<_>>();
+    for solver_name in SOLVER_NAMES {
+        let solve = solver_by_name(solver_name).unwrap();
+        let results = solve(
+            &problem,
+            chrono::Duration::milliseconds(5),
+            &mut StdRng::seed_from_u64(0),
+        );
+        // Everything else must hold for the people left without a seat
+        for person in problem.people.iter_mut() {
+            if results.solution.unassigned.contains(&person.id) {
+                person.unassigned_penalty.get_or_insert(0.0);
+            }
+        }
+        if let Err(error) = check_solution(&problem, &results.solution) {
+            panic!("{}: {}", solver_name, error);
+        }
+        for (person, unassigned_penalty) in problem.people.iter_mut().zip(&unassigned_penalties) {
+            person.unassigned_penalty = *unassigned_penalty;
+        }
+    }
+});
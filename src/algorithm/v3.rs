@@ -1,4 +1,7 @@
+use std::iter;
+
 use chrono::Utc;
+use itertools::Itertools;
 use rand::prelude::IteratorRandom;
 use rand::{thread_rng, Rng};
 use smallvec::{smallvec, SmallVec};
@@ -16,6 +19,7 @@ struct TableDaySolution {
 #[derive(Clone)]
 struct SolutionInner {
     solution_per_table: Vec<TableDaySolution>,
+    unassigned: Vec<PersonId>,
 }
 
 impl SolutionInner {
@@ -23,7 +27,21 @@ impl SolutionInner {
         self.solution_per_table
             .iter()
             .map(|tds| objective_value_calculator.table_value(tds.table_day_id, &tds.people))
-            .sum()
+            .sum::<f64>()
+            - self
+                .unassigned
+                .iter()
+                .map(|person_id| {
+                    objective_value_calculator
+                        .unassigned_penalty(*person_id)
+                        .unwrap_or_default()
+                })
+                .sum::<f64>()
+            - objective_value_calculator.repeated_neighbours_penalty(
+                self.solution_per_table
+                    .iter()
+                    .map(|tds| (tds.table_day_id, tds.people.as_slice())),
+            )
     }
 }
 
@@ -35,15 +53,25 @@ impl From<SolutionInner> for Solution {
                 .iter()
                 .map(|tds| (tds.table_day_id, tds.people.to_vec()))
                 .collect(),
+            unassigned: value.unassigned,
         }
     }
 }
 
 /// Smallvec for tables
 pub fn solve(input: &ProblemDescription, time_limit: chrono::Duration) -> AlgorithmResults {
+    solve_with_rng(input, time_limit, &mut thread_rng())
+}
+
+/// Same as solve, but with all randomness taken from the given generator
+pub fn solve_with_rng(
+    input: &ProblemDescription,
+    time_limit: chrono::Duration,
+    rng: &mut impl Rng,
+) -> AlgorithmResults {
     let start = Utc::now();
 
-    let calculator = ObjectiveValueCalculator::new(&input);
+    let calculator = ObjectiveValueCalculator::new(input);
 
     let mut solution = SolutionInner {
         solution_per_table: input
@@ -54,14 +82,16 @@ pub fn solve(input: &ProblemDescription, time_limit: chrono::Duration) -> Algori
                 people: smallvec![],
             })
             .collect(),
+        unassigned: vec![],
     };
 
-    // initial solution - find minimum cost
-    insert_into_best_positions(
-        &calculator,
-        &mut solution,
-        input.people.iter().map(|p| p.id),
-    );
+    // initial solution - find minimum cost. People without a seat left are left unassigned
+    // even if they must be seated, the validator then tells who is missing
+    for person_id in input.seatings_by_availability() {
+        if !insert_into_best_positions(&calculator, &mut solution, iter::once(person_id)) {
+            solution.unassigned.push(person_id);
+        }
+    }
 
     println!("Base Solution cost {}", solution.cost(&calculator));
 
@@ -69,7 +99,10 @@ pub fn solve(input: &ProblemDescription, time_limit: chrono::Duration) -> Algori
     let mut last_improved_iteration = 0;
     let mut current_cost = solution.cost(&calculator);
 
-    let max_no_to_remove_in_iteration = (input.people.len() / 20).max(input.people.len().min(5));
+    // Never below 3, so tiny problems still have a range of people to move
+    let max_no_to_remove_in_iteration = (input.people.len() / 20)
+        .max(input.people.len().min(5))
+        .max(3);
     loop {
         iteration += 1;
         if iteration - last_improved_iteration > 200 {
@@ -82,24 +115,33 @@ pub fn solve(input: &ProblemDescription, time_limit: chrono::Duration) -> Algori
         }
         let mut new_solution = solution.clone();
 
-        let no_people_to_move = thread_rng().gen_range(2..max_no_to_remove_in_iteration);
+        let no_people_to_move = rng.gen_range(2..max_no_to_remove_in_iteration);
 
         let mut people_to_move = vec![];
 
         for _ in 0..no_people_to_move {
-            let table_day = new_solution
+            let Some(table_day) = new_solution
                 .solution_per_table
                 .iter_mut()
                 .filter(|tds| !tds.people.is_empty())
-                .choose(&mut thread_rng())
-                .unwrap();
+                .choose(rng)
+            else {
+                // Fewer people are seated than there are to move
+                break;
+            };
 
-            let chosen_person_index = (0..table_day.people.len())
-                .choose(&mut thread_rng())
-                .unwrap();
+            let chosen_person_index = (0..table_day.people.len()).choose(rng).unwrap();
             people_to_move.push(table_day.people.remove(chosen_person_index))
         }
-        insert_into_best_positions(&calculator, &mut new_solution, people_to_move.into_iter());
+        // Give some of the people without a seat another chance
+        for _ in 0..no_people_to_move.min(new_solution.unassigned.len()) {
+            let index = rng.gen_range(0..new_solution.unassigned.len());
+            people_to_move.push(new_solution.unassigned.swap_remove(index));
+        }
+        if !insert_into_best_positions(&calculator, &mut new_solution, people_to_move.into_iter()) {
+            // Not everybody could be seated back, skip this move
+            continue;
+        }
 
         let new_cost = new_solution.cost(&calculator);
 
@@ -115,18 +157,27 @@ pub fn solve(input: &ProblemDescription, time_limit: chrono::Duration) -> Algori
     println!("Finished after {} iterations", iteration);
 
     AlgorithmResults {
+        objective_value: current_cost,
         solution: Solution::from(solution),
         no_of_iterations: iteration,
         elapsed: Utc::now() - start,
     }
 }
 
+/// Returns false if some person that must be seated has no table left they are available for
 fn insert_into_best_positions(
     calculator: &ObjectiveValueCalculator,
     solution: &mut SolutionInner,
     people_ids: impl Iterator<Item = PersonId>,
-) {
+) -> bool {
     for person_id in people_ids {
+        // Dates the person is already seated on
+        let occupied_dates = solution
+            .solution_per_table
+            .iter()
+            .filter(|tds| tds.people.contains(&person_id))
+            .map(|tds| calculator.table_date(tds.table_day_id))
+            .collect_vec();
         let mut best_insertion_description: Option<(usize, usize)> = None;
         let mut best_insertion_value = f64::MIN;
 
@@ -138,6 +189,11 @@ fn insert_into_best_positions(
             },
         ) in solution.solution_per_table.iter().enumerate()
         {
+            if !calculator.is_available(person_id, *table_day_id)
+                || occupied_dates.contains(&calculator.table_date(*table_day_id))
+            {
+                continue;
+            }
             if people.is_empty() {
                 let insertion_value = calculator.table_value(*table_day_id, &[person_id]);
                 if insertion_value > best_insertion_value {
@@ -146,23 +202,35 @@ fn insert_into_best_positions(
                 }
             } else if people.len() < MAX_PEOPLE_FOR_TABLE {
                 let current_cost = calculator.table_value(*table_day_id, people);
-                for insertion_index in 0..people.len() {
+                for insertion_index in 0..calculator
+                    .table_layout(*table_day_id)
+                    .insertion_positions(people.len())
+                {
                     let mut updated_people = people.clone();
                     updated_people.insert(insertion_index, person_id);
                     let insertion_value =
                         calculator.table_value(*table_day_id, &updated_people) - current_cost;
                     if insertion_value > best_insertion_value {
-                        best_insertion_description = Some((table_day_index, 0));
+                        best_insertion_description = Some((table_day_index, insertion_index));
                         best_insertion_value = insertion_value
                     }
                 }
             }
         }
 
-        let (insertion_table, insertion_index) =
-            best_insertion_description.expect("No possible insertions?");
-        solution.solution_per_table[insertion_table]
-            .people
-            .insert(insertion_index, person_id);
+        let unassigned_penalty = calculator.unassigned_penalty(person_id);
+        match best_insertion_description {
+            Some((insertion_table, insertion_index))
+                if unassigned_penalty.is_none_or(|penalty| best_insertion_value > -penalty) =>
+            {
+                solution.solution_per_table[insertion_table]
+                    .people
+                    .insert(insertion_index, person_id);
+            }
+            // Leaving the person without a seat is allowed and not worse than seating them
+            _ if unassigned_penalty.is_some() => solution.unassigned.push(person_id),
+            _ => return false,
+        }
     }
+    true
 }
@@ -1,4 +1,5 @@
 use std::hash::{Hash, Hasher};
+use std::iter;
 
 use ahash::AHashMap;
 use chrono::Utc;
@@ -34,6 +35,7 @@ type InsertionCache = AHashMap<TableDaySolution, AHashMap<PersonId, Option<f64>>
 #[derive(Clone)]
 struct SolutionInner {
     solution_per_table: Vec<TableDaySolution>,
+    unassigned: Vec<PersonId>,
 }
 
 impl SolutionInner {
@@ -41,7 +43,21 @@ impl SolutionInner {
         self.solution_per_table
             .iter()
             .map(|tds| objective_value_calculator.table_value(tds.table_day_id, &tds.people))
-            .sum()
+            .sum::<f64>()
+            - self
+                .unassigned
+                .iter()
+                .map(|person_id| {
+                    objective_value_calculator
+                        .unassigned_penalty(*person_id)
+                        .unwrap_or_default()
+                })
+                .sum::<f64>()
+            - objective_value_calculator.repeated_neighbours_penalty(
+                self.solution_per_table
+                    .iter()
+                    .map(|tds| (tds.table_day_id, tds.people.as_slice())),
+            )
     }
 }
 
@@ -53,15 +69,25 @@ impl From<SolutionInner> for Solution {
                 .iter()
                 .map(|tds| (tds.table_day_id, tds.people.to_vec()))
                 .collect(),
+            unassigned: value.unassigned,
         }
     }
 }
 
 /// Insertion cache
 pub fn solve(input: &ProblemDescription, time_limit: chrono::Duration) -> AlgorithmResults {
+    solve_with_rng(input, time_limit, &mut thread_rng())
+}
+
+/// Same as solve, but with all randomness taken from the given generator
+pub fn solve_with_rng(
+    input: &ProblemDescription,
+    time_limit: chrono::Duration,
+    rng: &mut impl Rng,
+) -> AlgorithmResults {
     let start = Utc::now();
 
-    let calculator = ObjectiveValueCalculator::new(&input);
+    let calculator = ObjectiveValueCalculator::new(input);
 
     let mut solution = SolutionInner {
         solution_per_table: input
@@ -72,23 +98,32 @@ pub fn solve(input: &ProblemDescription, time_limit: chrono::Duration) -> Algori
                 people: smallvec![],
             })
             .collect(),
+        unassigned: vec![],
     };
 
     let mut insertion_cache: InsertionCache = Default::default();
 
-    // initial solution - find minimum cost
-    insert_into_best_positions(
-        &calculator,
-        &mut insertion_cache,
-        &mut solution,
-        input.people.iter().map(|p| p.id),
-    );
+    // initial solution - find minimum cost. People without a seat left are left unassigned
+    // even if they must be seated, the validator then tells who is missing
+    for person_id in input.seatings_by_availability() {
+        if !insert_into_best_positions(
+            &calculator,
+            &mut insertion_cache,
+            &mut solution,
+            iter::once(person_id),
+        ) {
+            solution.unassigned.push(person_id);
+        }
+    }
 
     println!("Base Solution cost {}", solution.cost(&calculator));
 
     let mut iteration = 0;
     let mut last_improved_iteration = 0;
-    let max_no_to_remove_in_iteration = (input.people.len() / 20).max(input.people.len().min(5));
+    // Never below 3, so tiny problems still have a range of people to move
+    let max_no_to_remove_in_iteration = (input.people.len() / 20)
+        .max(input.people.len().min(5))
+        .max(3);
     let mut current_cost = solution.cost(&calculator);
     loop {
         iteration += 1;
@@ -102,29 +137,38 @@ pub fn solve(input: &ProblemDescription, time_limit: chrono::Duration) -> Algori
         }
         let mut new_solution = solution.clone();
 
-        let no_people_to_move = thread_rng().gen_range(2..max_no_to_remove_in_iteration);
+        let no_people_to_move = rng.gen_range(2..max_no_to_remove_in_iteration);
 
         let mut people_to_move = vec![];
 
         for _ in 0..no_people_to_move {
-            let table_day = new_solution
+            let Some(table_day) = new_solution
                 .solution_per_table
                 .iter_mut()
                 .filter(|tds| !tds.people.is_empty())
-                .choose(&mut thread_rng())
-                .unwrap();
+                .choose(rng)
+            else {
+                // Fewer people are seated than there are to move
+                break;
+            };
 
-            let chosen_person_index = (0..table_day.people.len())
-                .choose(&mut thread_rng())
-                .unwrap();
+            let chosen_person_index = (0..table_day.people.len()).choose(rng).unwrap();
             people_to_move.push(table_day.people.remove(chosen_person_index))
         }
-        insert_into_best_positions(
+        // Give some of the people without a seat another chance
+        for _ in 0..no_people_to_move.min(new_solution.unassigned.len()) {
+            let index = rng.gen_range(0..new_solution.unassigned.len());
+            people_to_move.push(new_solution.unassigned.swap_remove(index));
+        }
+        if !insert_into_best_positions(
             &calculator,
             &mut insertion_cache,
             &mut new_solution,
             people_to_move.into_iter(),
-        );
+        ) {
+            // Not everybody could be seated back, skip this move
+            continue;
+        }
 
         let new_cost = new_solution.cost(&calculator);
 
@@ -140,26 +184,38 @@ pub fn solve(input: &ProblemDescription, time_limit: chrono::Duration) -> Algori
     println!("Finished after {} iterations", iteration);
 
     AlgorithmResults {
+        objective_value: current_cost,
         solution: Solution::from(solution),
         no_of_iterations: iteration,
         elapsed: Utc::now() - start,
     }
 }
 
+/// Returns false if some person that must be seated has no table left they are available for
 fn insert_into_best_positions(
     calculator: &ObjectiveValueCalculator,
     insertion_cache: &mut InsertionCache,
     solution: &mut SolutionInner,
     people_to_insert: impl Iterator<Item = PersonId>,
-) {
+) -> bool {
     for person_to_insert in people_to_insert {
+        // Dates the person is already seated on
+        let occupied_dates = solution
+            .solution_per_table
+            .iter()
+            .filter(|tds| tds.people.contains(&person_to_insert))
+            .map(|tds| calculator.table_date(tds.table_day_id))
+            .collect_vec();
         let mut best_insertion_table: Option<usize> = None;
-        let best_insertion_value = f64::MIN;
+        let mut best_insertion_value = f64::MIN;
 
         for (table_day_index, table_day_solution) in solution.solution_per_table.iter().enumerate()
         {
+            if occupied_dates.contains(&calculator.table_date(table_day_solution.table_day_id)) {
+                continue;
+            }
             let value_optional = insertion_cache
-                .get(&table_day_solution)
+                .get(table_day_solution)
                 .and_then(|cache_for_table_day| cache_for_table_day.get(&person_to_insert).cloned())
                 .unwrap_or_else(|| {
                     let value_optional = insert_into_best_table_position(
@@ -178,19 +234,33 @@ fn insert_into_best_positions(
             if let Some(value) = value_optional {
                 if value > best_insertion_value {
                     best_insertion_table = Some(table_day_index);
+                    best_insertion_value = value;
                 }
             }
         }
 
-        let insertion_table = best_insertion_table.expect("No possible insertions?");
-        solution.solution_per_table[insertion_table] = insert_into_best_table_position(
-            calculator,
-            &solution.solution_per_table[insertion_table],
-            person_to_insert,
-        )
-        .unwrap()
-        .0;
+        let best_insertion = best_insertion_table.map(|insertion_table| {
+            let insertion = insert_into_best_table_position(
+                calculator,
+                &solution.solution_per_table[insertion_table],
+                person_to_insert,
+            )
+            .unwrap();
+            (insertion_table, insertion)
+        });
+        let unassigned_penalty = calculator.unassigned_penalty(person_to_insert);
+        match best_insertion {
+            Some((insertion_table, (table_day_solution, insertion_value)))
+                if unassigned_penalty.is_none_or(|penalty| insertion_value > -penalty) =>
+            {
+                solution.solution_per_table[insertion_table] = table_day_solution;
+            }
+            // Leaving the person without a seat is allowed and not worse than seating them
+            _ if unassigned_penalty.is_some() => solution.unassigned.push(person_to_insert),
+            _ => return false,
+        }
     }
+    true
 }
 
 fn insert_into_best_table_position(
@@ -198,21 +268,27 @@ fn insert_into_best_table_position(
     table_day_solution: &TableDaySolution,
     person_to_insert: PersonId,
 ) -> Option<(TableDaySolution, f64)> {
-    if table_day_solution.people.is_empty() {
+    if !calculator.is_available(person_to_insert, table_day_solution.table_day_id) {
+        // Person did not register for this date
+        None
+    } else if table_day_solution.people.is_empty() {
         let people = smallvec![person_to_insert];
         let insertion_value = calculator.table_value(table_day_solution.table_day_id, &people);
-        return Some((
+        Some((
             TableDaySolution {
                 table_day_id: table_day_solution.table_day_id,
                 people,
             },
             insertion_value,
-        ));
+        ))
     } else if table_day_solution.people.len() < MAX_PEOPLE_FOR_TABLE {
         let current_cost =
             calculator.table_value(table_day_solution.table_day_id, &table_day_solution.people);
         let mut best_solution: Option<(TableDaySolution, f64)> = None;
-        for insertion_index in 0..table_day_solution.people.len() {
+        for insertion_index in 0..calculator
+            .table_layout(table_day_solution.table_day_id)
+            .insertion_positions(table_day_solution.people.len())
+        {
             let mut updated_people = table_day_solution.people.clone();
             updated_people.insert(insertion_index, person_to_insert);
             let insertion_value = calculator
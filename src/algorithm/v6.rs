@@ -0,0 +1,366 @@
+use std::hash::Hasher;
+use std::iter;
+
+use ahash::AHashMap;
+use chrono::Utc;
+use itertools::Itertools;
+use rand::prelude::IteratorRandom;
+use rand::{thread_rng, Rng};
+use smallvec::{smallvec, SmallVec};
+
+use crate::objective_value_calculator::v6::ObjectiveValueCalculator;
+use crate::problem::{
+    AlgorithmResults, PersonId, ProblemDescription, Solution, TableDayId, TableLayout,
+    MAX_PEOPLE_FOR_TABLE,
+};
+
+type TableDaySolutionHash = u64;
+
+#[derive(Clone, PartialEq, Eq)]
+struct TableDaySolution {
+    table_day_id: TableDayId,
+    layout: TableLayout,
+    people: SmallVec<[PersonId; MAX_PEOPLE_FOR_TABLE]>,
+    hash: TableDaySolutionHash,
+}
+
+impl TableDaySolution {
+    fn new(
+        table_day_id: TableDayId,
+        layout: TableLayout,
+        people: SmallVec<[PersonId; MAX_PEOPLE_FOR_TABLE]>,
+    ) -> Self {
+        let hash = calculate_table_day_hash(table_day_id, layout, &people);
+        Self {
+            table_day_id,
+            layout,
+            people,
+            hash,
+        }
+    }
+
+    fn remove_person_on_index(&mut self, index: usize) -> PersonId {
+        let result = self.people.remove(index);
+        self.hash = calculate_table_day_hash(self.table_day_id, self.layout, &self.people);
+        result
+    }
+}
+
+fn calculate_table_day_hash(
+    table_day_id: TableDayId,
+    layout: TableLayout,
+    people: &[PersonId],
+) -> TableDaySolutionHash {
+    let mut hasher = fxhash::FxHasher64::default();
+    // FxHash ignores leading zeros, so without the length first table day 0 with person 0
+    // hashes the same as the empty table day 1
+    hasher.write_usize(people.len());
+    hasher.write_usize(table_day_id);
+    if layout == TableLayout::Ring {
+        // Rotating people around a round table does not change its value
+        if let Some(min_person_position) = people.iter().position_min() {
+            for index in 0..people.len() {
+                hasher.write_usize(people[(index + min_person_position) % people.len()])
+            }
+        }
+    } else {
+        for person_id in people {
+            hasher.write_usize(*person_id)
+        }
+    }
+    hasher.finish()
+}
+
+type InsertionCache = AHashMap<TableDaySolutionHash, AHashMap<PersonId, Option<f64>>>;
+
+#[derive(Clone)]
+struct SolutionInner {
+    solution_per_table: Vec<TableDaySolution>,
+    unassigned: Vec<PersonId>,
+}
+
+impl SolutionInner {
+    /// Full recalculation of the objective, the main loop keeps track of it incrementally
+    fn cost(&self, objective_value_calculator: &ObjectiveValueCalculator) -> f64 {
+        self.solution_per_table
+            .iter()
+            .map(|tds| objective_value_calculator.table_value(tds.table_day_id, &tds.people))
+            .sum::<f64>()
+            - self
+                .unassigned
+                .iter()
+                .map(|person_id| {
+                    objective_value_calculator
+                        .unassigned_penalty(*person_id)
+                        .unwrap_or_default()
+                })
+                .sum::<f64>()
+            - self.repeated_neighbours_penalty(objective_value_calculator)
+    }
+
+    /// The only part of the objective that is not tracked with deltas, zero unless
+    /// people are seated more than once
+    fn repeated_neighbours_penalty(
+        &self,
+        objective_value_calculator: &ObjectiveValueCalculator,
+    ) -> f64 {
+        objective_value_calculator.repeated_neighbours_penalty(
+            self.solution_per_table
+                .iter()
+                .map(|tds| (tds.table_day_id, tds.people.as_slice())),
+        )
+    }
+}
+
+impl From<SolutionInner> for Solution {
+    fn from(value: SolutionInner) -> Self {
+        Solution {
+            solution_per_table: value
+                .solution_per_table
+                .iter()
+                .map(|tds| (tds.table_day_id, tds.people.to_vec()))
+                .collect(),
+            unassigned: value.unassigned,
+        }
+    }
+}
+
+/// Running objective updated with calculator deltas
+pub fn solve(input: &ProblemDescription, time_limit: chrono::Duration) -> AlgorithmResults {
+    solve_with_rng(input, time_limit, &mut thread_rng())
+}
+
+/// Same as solve, but with all randomness taken from the given generator
+pub fn solve_with_rng(
+    input: &ProblemDescription,
+    time_limit: chrono::Duration,
+    rng: &mut impl Rng,
+) -> AlgorithmResults {
+    let start = Utc::now();
+
+    let calculator = ObjectiveValueCalculator::new(input);
+
+    let mut solution = SolutionInner {
+        solution_per_table: input
+            .tables
+            .iter()
+            .map(|t| TableDaySolution::new(t.id, t.layout, smallvec![]))
+            .collect(),
+        unassigned: vec![],
+    };
+
+    let mut insertion_cache: InsertionCache = Default::default();
+
+    // initial solution - find minimum cost. People without a seat left are left unassigned
+    // even if they must be seated, the validator then tells who is missing
+    for person_id in input.seatings_by_availability() {
+        if insert_into_best_positions(
+            &calculator,
+            &mut insertion_cache,
+            &mut solution,
+            iter::once(person_id),
+        )
+        .is_none()
+        {
+            solution.unassigned.push(person_id);
+        }
+    }
+
+    println!("Base Solution cost {}", solution.cost(&calculator));
+
+    let mut iteration = 0;
+    let mut last_improved_iteration = 0;
+    // Never below 3, so tiny problems still have a range of people to move
+    let max_no_to_remove_in_iteration = (input.people.len() / 20)
+        .max(input.people.len().min(5))
+        .max(3);
+    let mut current_cost = solution.cost(&calculator);
+    let mut current_repeated_neighbours_penalty = solution.repeated_neighbours_penalty(&calculator);
+
+    loop {
+        iteration += 1;
+        if iteration - last_improved_iteration > 200 {
+            println!("Terminated with no improvement");
+            break;
+        }
+        if Utc::now() - start > time_limit {
+            println!("Terminated with time limit");
+            break;
+        }
+        let mut new_solution = solution.clone();
+        // Objective without the repeated neighbours penalty
+        let mut new_cost = current_cost + current_repeated_neighbours_penalty;
+
+        let no_people_to_move = rng.gen_range(2..max_no_to_remove_in_iteration);
+
+        let mut people_to_move = vec![];
+
+        for _ in 0..no_people_to_move {
+            let Some(table_day) = new_solution
+                .solution_per_table
+                .iter_mut()
+                .filter(|tds| !tds.people.is_empty())
+                .choose(rng)
+            else {
+                // Fewer people are seated than there are to move
+                break;
+            };
+
+            let chosen_person_index = (0..table_day.people.len()).choose(rng).unwrap();
+            new_cost += calculator.removal_delta(
+                table_day.table_day_id,
+                &table_day.people,
+                chosen_person_index,
+            );
+            people_to_move.push(table_day.remove_person_on_index(chosen_person_index))
+        }
+        // Give some of the people without a seat another chance
+        for _ in 0..no_people_to_move.min(new_solution.unassigned.len()) {
+            let index = rng.gen_range(0..new_solution.unassigned.len());
+            let person_id = new_solution.unassigned.swap_remove(index);
+            new_cost += calculator.unassigned_penalty(person_id).unwrap_or_default();
+            people_to_move.push(person_id);
+        }
+        let Some(insertion_delta) = insert_into_best_positions(
+            &calculator,
+            &mut insertion_cache,
+            &mut new_solution,
+            people_to_move.into_iter(),
+        ) else {
+            // Not everybody could be seated back, skip this move
+            continue;
+        };
+
+        let new_repeated_neighbours_penalty = new_solution.repeated_neighbours_penalty(&calculator);
+        new_cost += insertion_delta - new_repeated_neighbours_penalty;
+
+        if new_cost > current_cost {
+            solution = new_solution;
+            last_improved_iteration = iteration;
+            current_cost = new_cost;
+            current_repeated_neighbours_penalty = new_repeated_neighbours_penalty;
+        }
+    }
+
+    println!("Final Solution cost {}", solution.cost(&calculator));
+
+    println!("Finished after {} iterations", iteration);
+
+    AlgorithmResults {
+        objective_value: current_cost,
+        solution: Solution::from(solution),
+        no_of_iterations: iteration,
+        elapsed: Utc::now() - start,
+    }
+}
+
+/// Returns the change of the objective without the repeated neighbours penalty,
+/// or None if some person that must be seated has no table left they are available for
+fn insert_into_best_positions(
+    calculator: &ObjectiveValueCalculator,
+    insertion_cache: &mut InsertionCache,
+    solution: &mut SolutionInner,
+    people_to_insert: impl Iterator<Item = PersonId>,
+) -> Option<f64> {
+    let mut objective_change = 0.0;
+    for person_to_insert in people_to_insert {
+        // Dates the person is already seated on
+        let occupied_dates = solution
+            .solution_per_table
+            .iter()
+            .filter(|tds| tds.people.contains(&person_to_insert))
+            .map(|tds| calculator.table_date(tds.table_day_id))
+            .collect_vec();
+        let mut best_insertion_table: Option<usize> = None;
+        let mut best_insertion_value = f64::MIN;
+
+        for (table_day_index, table_day_solution) in solution.solution_per_table.iter().enumerate()
+        {
+            if occupied_dates.contains(&calculator.table_date(table_day_solution.table_day_id)) {
+                continue;
+            }
+            let value_optional = insertion_cache
+                .get(&table_day_solution.hash)
+                .and_then(|cache_for_table_day| cache_for_table_day.get(&person_to_insert).cloned())
+                .unwrap_or_else(|| {
+                    let value_optional = insert_into_best_table_position(
+                        calculator,
+                        table_day_solution,
+                        person_to_insert,
+                    )
+                    .map(|(_, v)| v);
+                    insertion_cache
+                        .entry(table_day_solution.hash)
+                        .or_default()
+                        .insert(person_to_insert, value_optional);
+                    value_optional
+                });
+
+            if let Some(value) = value_optional {
+                if value > best_insertion_value {
+                    best_insertion_table = Some(table_day_index);
+                    best_insertion_value = value;
+                }
+            }
+        }
+
+        let unassigned_penalty = calculator.unassigned_penalty(person_to_insert);
+        match best_insertion_table {
+            Some(insertion_table)
+                if unassigned_penalty.is_none_or(|penalty| best_insertion_value > -penalty) =>
+            {
+                let (people, insertion_value) = insert_into_best_table_position(
+                    calculator,
+                    &solution.solution_per_table[insertion_table],
+                    person_to_insert,
+                )
+                .unwrap();
+                objective_change += insertion_value;
+                solution.solution_per_table[insertion_table] = TableDaySolution::new(
+                    solution.solution_per_table[insertion_table].table_day_id,
+                    solution.solution_per_table[insertion_table].layout,
+                    people,
+                );
+            }
+            // Leaving the person without a seat is allowed and not worse than seating them
+            _ => {
+                objective_change -= unassigned_penalty?;
+                solution.unassigned.push(person_to_insert);
+            }
+        }
+    }
+    Some(objective_change)
+}
+
+fn insert_into_best_table_position(
+    calculator: &ObjectiveValueCalculator,
+    table_day_solution: &TableDaySolution,
+    person_to_insert: PersonId,
+) -> Option<(SmallVec<[PersonId; MAX_PEOPLE_FOR_TABLE]>, f64)> {
+    if !calculator.is_available(person_to_insert, table_day_solution.table_day_id)
+        || table_day_solution.people.len() >= MAX_PEOPLE_FOR_TABLE
+    {
+        // Person did not register for this date or table at full capacity
+        return None;
+    }
+    let mut best_insertion: Option<(usize, f64)> = None;
+    for insertion_index in 0..table_day_solution
+        .layout
+        .insertion_positions(table_day_solution.people.len())
+    {
+        let insertion_value = calculator.insertion_delta(
+            table_day_solution.table_day_id,
+            &table_day_solution.people,
+            insertion_index,
+            person_to_insert,
+        );
+        if insertion_value > best_insertion.map(|(_, v)| v).unwrap_or(f64::MIN) {
+            best_insertion = Some((insertion_index, insertion_value))
+        }
+    }
+    best_insertion.map(|(insertion_index, insertion_value)| {
+        let mut updated_people = table_day_solution.people.clone();
+        updated_people.insert(insertion_index, person_to_insert);
+        (updated_people, insertion_value)
+    })
+}
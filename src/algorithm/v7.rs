@@ -0,0 +1,259 @@
+use std::iter;
+
+use chrono::Utc;
+use rand::prelude::IteratorRandom;
+use rand::{thread_rng, Rng};
+
+use crate::objective_value_calculator::v7::{ObjectiveValueCalculator, TableDaysSeating};
+use crate::problem::{AlgorithmResults, ProblemDescription, Solution};
+
+#[derive(Clone)]
+struct SolutionInner {
+    seating: TableDaysSeating,
+    /// Person indexes
+    unassigned: Vec<usize>,
+}
+
+impl SolutionInner {
+    /// Full recalculation of the objective, the main loop keeps track of it incrementally
+    fn cost(&self, objective_value_calculator: &ObjectiveValueCalculator) -> f64 {
+        (0..objective_value_calculator.no_of_table_days())
+            .map(|table_day_index| {
+                objective_value_calculator
+                    .table_value_by_index(table_day_index, &self.seating.people(table_day_index))
+            })
+            .sum::<f64>()
+            - self
+                .unassigned
+                .iter()
+                .map(|person_index| {
+                    objective_value_calculator
+                        .unassigned_penalty_by_index(*person_index)
+                        .unwrap_or_default()
+                })
+                .sum::<f64>()
+            - objective_value_calculator.seating_repeated_neighbours_penalty(&self.seating)
+    }
+
+    fn into_solution(self, objective_value_calculator: &ObjectiveValueCalculator) -> Solution {
+        Solution {
+            solution_per_table: (0..objective_value_calculator.no_of_table_days())
+                .map(|table_day_index| {
+                    (
+                        objective_value_calculator.table_day_id(table_day_index),
+                        self.seating
+                            .people(table_day_index)
+                            .iter()
+                            .map(|person_index| objective_value_calculator.person_id(*person_index))
+                            .collect(),
+                    )
+                })
+                .collect(),
+            unassigned: self
+                .unassigned
+                .iter()
+                .map(|person_index| objective_value_calculator.person_id(*person_index))
+                .collect(),
+        }
+    }
+}
+
+/// Batch insertion scoring over all table days with calculator v7
+pub fn solve(input: &ProblemDescription, time_limit: chrono::Duration) -> AlgorithmResults {
+    solve_with_rng(input, time_limit, &mut thread_rng())
+}
+
+/// Same as solve, but with all randomness taken from the given generator
+pub fn solve_with_rng(
+    input: &ProblemDescription,
+    time_limit: chrono::Duration,
+    rng: &mut impl Rng,
+) -> AlgorithmResults {
+    let start = Utc::now();
+
+    let calculator = ObjectiveValueCalculator::new(input);
+
+    let mut solution = SolutionInner {
+        seating: TableDaysSeating::new(&calculator),
+        unassigned: vec![],
+    };
+    let mut insertion_values = vec![];
+    let mut insertion_positions = vec![];
+
+    // initial solution - find minimum cost. People without a seat left are left unassigned
+    // even if they must be seated, the validator then tells who is missing
+    for person_index in input
+        .seatings_by_availability()
+        .map(|person_id| calculator.person_index(person_id))
+    {
+        if insert_into_best_positions(
+            &calculator,
+            &mut solution,
+            iter::once(person_index),
+            &mut insertion_values,
+            &mut insertion_positions,
+        )
+        .is_none()
+        {
+            solution.unassigned.push(person_index);
+        }
+    }
+
+    println!("Base Solution cost {}", solution.cost(&calculator));
+
+    let mut iteration = 0;
+    let mut last_improved_iteration = 0;
+    // Never below 3, so tiny problems still have a range of people to move
+    let max_no_to_remove_in_iteration = (input.people.len() / 20)
+        .max(input.people.len().min(5))
+        .max(3);
+    let mut current_cost = solution.cost(&calculator);
+    let mut current_repeated_neighbours_penalty =
+        calculator.seating_repeated_neighbours_penalty(&solution.seating);
+
+    loop {
+        iteration += 1;
+        if iteration - last_improved_iteration > 200 {
+            println!("Terminated with no improvement");
+            break;
+        }
+        if Utc::now() - start > time_limit {
+            println!("Terminated with time limit");
+            break;
+        }
+        let mut new_solution = solution.clone();
+        // Objective without the repeated neighbours penalty
+        let mut new_cost = current_cost + current_repeated_neighbours_penalty;
+
+        let no_people_to_move = rng.gen_range(2..max_no_to_remove_in_iteration);
+
+        let mut people_to_move = vec![];
+
+        for _ in 0..no_people_to_move {
+            let Some(table_day_index) = (0..calculator.no_of_table_days())
+                .filter(|table_day_index| new_solution.seating.no_of_people(*table_day_index) > 0)
+                .choose(rng)
+            else {
+                // Fewer people are seated than there are to move
+                break;
+            };
+
+            let chosen_seat = rng.gen_range(0..new_solution.seating.no_of_people(table_day_index));
+            new_cost +=
+                calculator.removal_value(&new_solution.seating, table_day_index, chosen_seat);
+            people_to_move.push(new_solution.seating.remove(
+                &calculator,
+                table_day_index,
+                chosen_seat,
+            ))
+        }
+        // Give some of the people without a seat another chance
+        for _ in 0..no_people_to_move.min(new_solution.unassigned.len()) {
+            let index = rng.gen_range(0..new_solution.unassigned.len());
+            let person_index = new_solution.unassigned.swap_remove(index);
+            new_cost += calculator
+                .unassigned_penalty_by_index(person_index)
+                .unwrap_or_default();
+            people_to_move.push(person_index);
+        }
+        let Some(insertion_delta) = insert_into_best_positions(
+            &calculator,
+            &mut new_solution,
+            people_to_move.into_iter(),
+            &mut insertion_values,
+            &mut insertion_positions,
+        ) else {
+            // Not everybody could be seated back, skip this move
+            continue;
+        };
+
+        let new_repeated_neighbours_penalty =
+            calculator.seating_repeated_neighbours_penalty(&new_solution.seating);
+        new_cost += insertion_delta - new_repeated_neighbours_penalty;
+
+        if new_cost > current_cost {
+            solution = new_solution;
+            last_improved_iteration = iteration;
+            current_cost = new_cost;
+            current_repeated_neighbours_penalty = new_repeated_neighbours_penalty;
+        }
+    }
+
+    println!("Final Solution cost {}", solution.cost(&calculator));
+
+    println!("Finished after {} iterations", iteration);
+
+    AlgorithmResults {
+        objective_value: current_cost,
+        solution: solution.into_solution(&calculator),
+        no_of_iterations: iteration,
+        elapsed: Utc::now() - start,
+    }
+}
+
+/// Returns the change of the objective without the repeated neighbours penalty,
+/// or None if some person that must be seated has no table left they are available for
+fn insert_into_best_positions(
+    calculator: &ObjectiveValueCalculator,
+    solution: &mut SolutionInner,
+    people_to_insert: impl Iterator<Item = usize>,
+    insertion_values: &mut Vec<f64>,
+    insertion_positions: &mut Vec<usize>,
+) -> Option<f64> {
+    let mut objective_change = 0.0;
+    for person_to_insert in people_to_insert {
+        calculator.insertion_values(
+            &solution.seating,
+            person_to_insert,
+            insertion_values,
+            insertion_positions,
+        );
+        if calculator.has_multiple_seatings() {
+            // Dates the person is already seated on
+            let occupied_dates = (0..calculator.no_of_table_days())
+                .filter(|table_day_index| {
+                    solution
+                        .seating
+                        .people(*table_day_index)
+                        .contains(&person_to_insert)
+                })
+                .map(|table_day_index| calculator.table_date_by_index(table_day_index))
+                .collect::<Vec<_>>();
+            for (table_day_index, value) in insertion_values.iter_mut().enumerate() {
+                if occupied_dates.contains(&calculator.table_date_by_index(table_day_index)) {
+                    *value = f64::NEG_INFINITY;
+                }
+            }
+        }
+
+        let mut best_insertion_table: Option<usize> = None;
+        let mut best_insertion_value = f64::MIN;
+        for (table_day_index, value) in insertion_values.iter().enumerate() {
+            if *value > best_insertion_value {
+                best_insertion_table = Some(table_day_index);
+                best_insertion_value = *value;
+            }
+        }
+
+        let unassigned_penalty = calculator.unassigned_penalty_by_index(person_to_insert);
+        match best_insertion_table {
+            Some(insertion_table)
+                if unassigned_penalty.is_none_or(|penalty| best_insertion_value > -penalty) =>
+            {
+                objective_change += best_insertion_value;
+                solution.seating.insert(
+                    calculator,
+                    insertion_table,
+                    insertion_positions[insertion_table],
+                    person_to_insert,
+                );
+            }
+            // Leaving the person without a seat is allowed and not worse than seating them
+            _ => {
+                objective_change -= unassigned_penalty?;
+                solution.unassigned.push(person_to_insert);
+            }
+        }
+    }
+    Some(objective_change)
+}
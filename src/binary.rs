@@ -0,0 +1,159 @@
+//! MessagePack problem and solution files, for large problems where JSON is slow to parse.
+//! Files ending with .msgpack are binary, every other file is JSON.
+//!
+//! Problems are stored with the schema version next to their fields like JSON problem files,
+//! and files of older versions are upgraded with the same migrations.
+
+use std::fmt;
+use std::path::Path;
+
+use serde::de::{self, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+use serde_json::{Map, Value};
+
+use crate::problem::{ProblemDescription, Solution};
+use crate::schema::{migrate, ProblemFileRef, SchemaError, VersionField};
+
+pub const BINARY_EXTENSION: &str = "msgpack";
+
+pub fn is_binary(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension == BINARY_EXTENSION)
+}
+
+#[derive(Debug)]
+pub enum BinaryError {
+    Decode(rmp_serde::decode::Error),
+    /// The file could not be upgraded to the current version or read after upgrading
+    Schema(SchemaError),
+}
+
+impl fmt::Display for BinaryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BinaryError::Decode(error) => write!(f, "{}", error),
+            BinaryError::Schema(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for BinaryError {}
+
+impl From<rmp_serde::decode::Error> for BinaryError {
+    fn from(error: rmp_serde::decode::Error) -> Self {
+        BinaryError::Decode(error)
+    }
+}
+
+impl From<SchemaError> for BinaryError {
+    fn from(error: SchemaError) -> Self {
+        BinaryError::Schema(error)
+    }
+}
+
+/// Fields are written with their names, so optional fields can be left out like in JSON
+pub fn problem_to_msgpack(
+    problem: &ProblemDescription,
+) -> Result<Vec<u8>, rmp_serde::encode::Error> {
+    rmp_serde::to_vec_named(&ProblemFileRef::new(problem))
+}
+
+/// Decodes any MessagePack value into the JSON tree migrations work on. Map keys may be
+/// integers in MessagePack, they become strings like in JSON files.
+struct JsonTree(Value);
+
+impl<'de> Deserialize<'de> for JsonTree {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(JsonTreeVisitor).map(JsonTree)
+    }
+}
+
+struct JsonTreeVisitor;
+
+impl<'de> Visitor<'de> for JsonTreeVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a value that can be written as JSON")
+    }
+
+    fn visit_bool<E: de::Error>(self, value: bool) -> Result<Value, E> {
+        Ok(value.into())
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Value, E> {
+        Ok(value.into())
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Value, E> {
+        Ok(value.into())
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Value, E> {
+        Ok(value.into())
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Value, E> {
+        Ok(value.into())
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut values = vec![];
+        while let Some(JsonTree(value)) = seq.next_element()? {
+            values.push(value);
+        }
+        Ok(Value::Array(values))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut values = Map::new();
+        while let Some((JsonTree(key), JsonTree(value))) = map.next_entry()? {
+            let key = match key {
+                Value::String(key) => key,
+                Value::Number(key) => key.to_string(),
+                key => return Err(de::Error::custom(format!("Map key {} is not allowed", key))),
+            };
+            values.insert(key, value);
+        }
+        Ok(Value::Object(values))
+    }
+}
+
+/// Reads a problem file of any supported version
+pub fn problem_from_msgpack(bytes: &[u8]) -> Result<ProblemDescription, BinaryError> {
+    // Files of the current version are read directly, without building a JSON tree first
+    if rmp_serde::from_slice(bytes)
+        .is_ok_and(|version_field: VersionField| version_field.is_current())
+    {
+        return Ok(rmp_serde::from_slice(bytes)?);
+    }
+    let JsonTree(value) = rmp_serde::from_slice(bytes)?;
+    if !value.is_object() {
+        return Err(BinaryError::Decode(de::Error::custom(
+            "Problem must be a map of its fields",
+        )));
+    }
+    let mut value = migrate(value)?;
+    value.as_object_mut().unwrap().remove("schema_version");
+    Ok(serde_json::from_value(value).map_err(SchemaError::from)?)
+}
+
+pub fn solution_to_msgpack(solution: &Solution) -> Result<Vec<u8>, rmp_serde::encode::Error> {
+    rmp_serde::to_vec_named(solution)
+}
+
+pub fn solution_from_msgpack(bytes: &[u8]) -> Result<Solution, BinaryError> {
+    Ok(rmp_serde::from_slice(bytes)?)
+}
@@ -0,0 +1,347 @@
+use std::fmt::Write;
+
+use itertools::{Either, Itertools};
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::algorithm;
+use crate::generator::metrics::same_group_pair_share;
+use crate::objective_value_calculator::v1::ObjectiveValueCalculator;
+use crate::problem::{AlgorithmResults, PersonId, ProblemDescription};
+use crate::validator::{check_solution, validate_problem};
+
+pub type Solver = fn(&ProblemDescription, chrono::Duration, &mut StdRng) -> AlgorithmResults;
+
+pub const SOLVER_NAMES: [&str; 7] = ["v1", "v2", "v3", "v4", "v5", "v6", "v7"];
+
+pub fn solver_by_name(name: &str) -> Option<Solver> {
+    match name {
+        "v1" => Some(algorithm::v1::solve_with_rng),
+        "v2" => Some(algorithm::v2::solve_with_rng),
+        "v3" => Some(algorithm::v3::solve_with_rng),
+        "v4" => Some(algorithm::v4::solve_with_rng),
+        "v5" => Some(algorithm::v5::solve_with_rng),
+        "v6" => Some(algorithm::v6::solve_with_rng),
+        "v7" => Some(algorithm::v7::solve_with_rng),
+        _ => None,
+    }
+}
+
+/// Problem to run the solvers on
+#[derive(Debug)]
+pub struct ExperimentProblem {
+    pub name: String,
+    pub problem: ProblemDescription,
+    /// Groups the relations were planted in, to report how well solvers recover them
+    pub groups: Option<Vec<Vec<PersonId>>>,
+}
+
+/// Outcome of one solver on one problem with one seed and time limit
+#[derive(Debug, Clone)]
+pub struct ExperimentRun {
+    pub solver: String,
+    pub time_limit_ms: i64,
+    pub problem: String,
+    pub seed: u64,
+    /// Why there is no valid solution, the problem is invalid or the solver broke the rules
+    pub result: Result<RunResult, String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RunResult {
+    pub objective: f64,
+    pub no_of_iterations: usize,
+    pub elapsed_ms: f64,
+    /// None when the problem has no planted groups or no table has two people
+    pub same_group_pair_share: Option<f64>,
+}
+
+/// Runs every solver on every problem with every seed and time limit. Invalid problems are
+/// not solved and invalid solutions are not scored, both are reported as failed runs.
+pub fn run_experiment(
+    problems: &[ExperimentProblem],
+    solvers: &[String],
+    seeds: &[u64],
+    time_limits_ms: &[i64],
+) -> Vec<ExperimentRun> {
+    let mut runs = vec![];
+    for ExperimentProblem {
+        name: problem_name,
+        problem,
+        groups,
+    } in problems
+    {
+        let calculator = validate_problem(problem)
+            .map(|()| ObjectiveValueCalculator::new(problem))
+            .map_err(|error| format!("Invalid problem: {}", error));
+        for solver_name in solvers {
+            let solve = solver_by_name(solver_name).expect("Unknown solver");
+            for time_limit_ms in time_limits_ms {
+                for seed in seeds {
+                    println!(
+                        "--- Running {} for {} with seed {} and time limit {}ms",
+                        solver_name, problem_name, seed, time_limit_ms
+                    );
+                    let result = match &calculator {
+                        Err(error) => Err(error.clone()),
+                        Ok(calculator) => {
+                            let result = solve(
+                                problem,
+                                chrono::Duration::milliseconds(*time_limit_ms),
+                                &mut StdRng::seed_from_u64(*seed),
+                            );
+                            check_solution(problem, &result.solution)
+                                .map(|()| RunResult {
+                                    objective: calculator.solution_value(&result.solution),
+                                    no_of_iterations: result.no_of_iterations,
+                                    elapsed_ms: result
+                                        .elapsed
+                                        .num_microseconds()
+                                        .unwrap_or(i64::MAX)
+                                        as f64
+                                        / 1000.0,
+                                    same_group_pair_share: groups.as_ref().and_then(|groups| {
+                                        same_group_pair_share(groups, &result.solution)
+                                    }),
+                                })
+                                .map_err(|error| format!("Invalid solution: {}", error))
+                        }
+                    };
+                    if let Err(error) = &result {
+                        println!("Failed: {}", error);
+                    }
+                    runs.push(ExperimentRun {
+                        solver: solver_name.clone(),
+                        time_limit_ms: *time_limit_ms,
+                        problem: problem_name.clone(),
+                        seed: *seed,
+                        result,
+                    });
+                }
+            }
+        }
+    }
+    runs
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Statistics {
+    pub mean: f64,
+    pub median: f64,
+    /// Population standard deviation
+    pub std: f64,
+}
+
+impl Statistics {
+    pub fn new(values: &[f64]) -> Self {
+        if values.is_empty() {
+            return Self {
+                mean: f64::NAN,
+                median: f64::NAN,
+                std: f64::NAN,
+            };
+        }
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        let mut sorted = values.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        let median = if sorted.len().is_multiple_of(2) {
+            (sorted[sorted.len() / 2 - 1] + sorted[sorted.len() / 2]) / 2.0
+        } else {
+            sorted[sorted.len() / 2]
+        };
+        let std =
+            (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64).sqrt();
+        Self { mean, median, std }
+    }
+}
+
+/// All runs of one solver with one time limit, over every problem and seed
+#[derive(Debug, Clone)]
+pub struct SolverSummary {
+    pub solver: String,
+    pub time_limit_ms: i64,
+    /// Runs with a valid solution, the statistics are over them
+    pub no_of_runs: usize,
+    /// Problem, seed and error of every run without a valid solution
+    pub failed_runs: Vec<(String, u64, String)>,
+    pub objective: Statistics,
+    pub iterations: Statistics,
+    pub elapsed_ms: Statistics,
+    /// Over the runs on problems with planted groups, None if there are none
+    pub same_group_pair_share: Option<Statistics>,
+}
+
+/// Summaries in the order solvers and time limits first appear in the runs
+pub fn summarize(runs: &[ExperimentRun]) -> Vec<SolverSummary> {
+    let mut keys: Vec<(&str, i64)> = vec![];
+    for run in runs {
+        if !keys.contains(&(run.solver.as_str(), run.time_limit_ms)) {
+            keys.push((run.solver.as_str(), run.time_limit_ms));
+        }
+    }
+    keys.into_iter()
+        .map(|(solver, time_limit_ms)| {
+            let (results, failed_runs): (Vec<_>, Vec<_>) = runs
+                .iter()
+                .filter(|r| r.solver == solver && r.time_limit_ms == time_limit_ms)
+                .partition_map(|r| match &r.result {
+                    Ok(result) => Either::Left(*result),
+                    Err(error) => Either::Right((r.problem.clone(), r.seed, error.clone())),
+                });
+            let statistics = |value: fn(&RunResult) -> f64| {
+                Statistics::new(&results.iter().map(value).collect::<Vec<_>>())
+            };
+            let same_group_pair_shares = results
+                .iter()
+                .filter_map(|r| r.same_group_pair_share)
+                .collect_vec();
+            SolverSummary {
+                solver: solver.to_string(),
+                time_limit_ms,
+                no_of_runs: results.len(),
+                failed_runs,
+                objective: statistics(|r| r.objective),
+                iterations: statistics(|r| r.no_of_iterations as f64),
+                elapsed_ms: statistics(|r| r.elapsed_ms),
+                same_group_pair_share: (!same_group_pair_shares.is_empty())
+                    .then(|| Statistics::new(&same_group_pair_shares)),
+            }
+        })
+        .collect()
+}
+
+/// Whether any run was on a problem with planted groups, only then reports have their columns
+fn has_groups(summaries: &[SolverSummary]) -> bool {
+    summaries
+        .iter()
+        .any(|summary| summary.same_group_pair_share.is_some())
+}
+
+/// Table of the summaries followed by the failed runs, if there are any
+pub fn markdown_report(summaries: &[SolverSummary]) -> String {
+    let has_groups = has_groups(summaries);
+    let mut report = String::new();
+    write!(
+        report,
+        "| solver | time limit ms | runs | failed | objective mean | objective median | objective std \
+         | iterations mean | iterations median | iterations std \
+         | elapsed ms mean | elapsed ms median | elapsed ms std |"
+    )
+    .unwrap();
+    if has_groups {
+        report
+            .push_str(" same group pairs mean | same group pairs median | same group pairs std |");
+    }
+    report.push('\n');
+    write!(
+        report,
+        "|--------|--------------:|-----:|-------:|---------------:|-----------------:|--------------:\
+         |----------------:|------------------:|---------------:\
+         |----------------:|------------------:|---------------:|"
+    )
+    .unwrap();
+    if has_groups {
+        report
+            .push_str("----------------------:|------------------------:|---------------------:|");
+    }
+    report.push('\n');
+    for summary in summaries {
+        write!(
+            report,
+            "| {} | {} | {} | {} | {:.3} | {:.3} | {:.3} | {:.1} | {:.1} | {:.1} | {:.1} | {:.1} | {:.1} |",
+            summary.solver,
+            summary.time_limit_ms,
+            summary.no_of_runs,
+            summary.failed_runs.len(),
+            summary.objective.mean,
+            summary.objective.median,
+            summary.objective.std,
+            summary.iterations.mean,
+            summary.iterations.median,
+            summary.iterations.std,
+            summary.elapsed_ms.mean,
+            summary.elapsed_ms.median,
+            summary.elapsed_ms.std,
+        )
+        .unwrap();
+        match summary.same_group_pair_share {
+            Some(share) => write!(
+                report,
+                " {:.3} | {:.3} | {:.3} |",
+                share.mean, share.median, share.std
+            )
+            .unwrap(),
+            None if has_groups => report.push_str("  |  |  |"),
+            None => {}
+        }
+        report.push('\n');
+    }
+    let failed_runs = summaries
+        .iter()
+        .flat_map(|summary| {
+            summary
+                .failed_runs
+                .iter()
+                .map(move |failed_run| (summary, failed_run))
+        })
+        .collect_vec();
+    if !failed_runs.is_empty() {
+        report.push_str("\nFailed runs:\n\n");
+        for (summary, (problem, seed, error)) in failed_runs {
+            writeln!(
+                report,
+                "- {} with seed {} and time limit {}ms on {}: {}",
+                summary.solver, seed, summary.time_limit_ms, problem, error
+            )
+            .unwrap();
+        }
+    }
+    report
+}
+
+pub fn csv_report(summaries: &[SolverSummary]) -> String {
+    let has_groups = has_groups(summaries);
+    let mut report = String::new();
+    write!(
+        report,
+        "solver,time_limit_ms,runs,failed,\
+         objective_mean,objective_median,objective_std,\
+         iterations_mean,iterations_median,iterations_std,\
+         elapsed_ms_mean,elapsed_ms_median,elapsed_ms_std"
+    )
+    .unwrap();
+    if has_groups {
+        report.push_str(",same_group_pairs_mean,same_group_pairs_median,same_group_pairs_std");
+    }
+    report.push('\n');
+    for summary in summaries {
+        write!(
+            report,
+            "{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            summary.solver,
+            summary.time_limit_ms,
+            summary.no_of_runs,
+            summary.failed_runs.len(),
+            summary.objective.mean,
+            summary.objective.median,
+            summary.objective.std,
+            summary.iterations.mean,
+            summary.iterations.median,
+            summary.iterations.std,
+            summary.elapsed_ms.mean,
+            summary.elapsed_ms.median,
+            summary.elapsed_ms.std,
+        )
+        .unwrap();
+        match summary.same_group_pair_share {
+            Some(share) => {
+                write!(report, ",{},{},{}", share.mean, share.median, share.std).unwrap()
+            }
+            None if has_groups => report.push_str(",,,"),
+            None => {}
+        }
+        report.push('\n');
+    }
+    report
+}
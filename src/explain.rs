@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use chrono::NaiveDate;
+
+use crate::objective_value_calculator::v1::ObjectiveValueCalculator;
+use crate::problem::{PersonId, ProblemDescription, Solution, TableDayId, TableId, TableLayout};
+
+/// Objective value of a solution split into the parts it adds up from, as computed by v1
+#[derive(Debug, Clone)]
+pub struct Explanation {
+    /// Ordered by table day id
+    pub tables: Vec<TableExplanation>,
+    /// Penalty of every person left without a seat
+    pub unassigned: Vec<(PersonId, f64)>,
+    pub repeated_neighbours_penalty: f64,
+    pub total: f64,
+    /// Names of the people who have one, shown next to their ids
+    pub person_names: HashMap<PersonId, String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TableExplanation {
+    pub table_day_id: TableDayId,
+    pub table_id: TableId,
+    pub name: Option<String>,
+    pub date: NaiveDate,
+    pub layout: TableLayout,
+    /// Score of every person regardless of neighbours: last visit recency, new table and
+    /// new weekday
+    pub people: Vec<(PersonId, [f64; 3])>,
+    /// Relation score of every pair of neighbours
+    pub neighbours: Vec<(PersonId, PersonId, f64)>,
+    pub value: f64,
+}
+
+pub fn explain(input: &ProblemDescription, solution: &Solution) -> Explanation {
+    let calculator = ObjectiveValueCalculator::new(input);
+    let mut tables = input
+        .tables
+        .iter()
+        .filter_map(|table| {
+            let people_ids = solution.solution_per_table.get(&table.id)?;
+            let people = people_ids
+                .iter()
+                .map(|person_id| (*person_id, calculator.person_score(*person_id, table.id)))
+                .collect::<Vec<_>>();
+            let neighbours = table
+                .layout
+                .neighbour_seats(people_ids.len())
+                .into_iter()
+                .map(|(seat, other_seat)| {
+                    let (person_1_id, person_2_id) = (people_ids[seat], people_ids[other_seat]);
+                    (
+                        person_1_id,
+                        person_2_id,
+                        calculator.get_relation_score(&person_1_id, &person_2_id),
+                    )
+                })
+                .collect::<Vec<_>>();
+            Some(TableExplanation {
+                table_day_id: table.id,
+                table_id: table.table_id,
+                name: table.name.clone(),
+                date: table.date.date_naive(),
+                layout: table.layout,
+                value: calculator.table_value(table.id, people_ids),
+                people,
+                neighbours,
+            })
+        })
+        .collect::<Vec<_>>();
+    tables.sort_by_key(|t| t.table_day_id);
+
+    let unassigned = solution
+        .unassigned
+        .iter()
+        .map(|person_id| {
+            (
+                *person_id,
+                calculator
+                    .unassigned_penalty(*person_id)
+                    .unwrap_or_default(),
+            )
+        })
+        .collect::<Vec<_>>();
+    let repeated_neighbours_penalty = calculator.repeated_neighbours_penalty(
+        solution
+            .solution_per_table
+            .iter()
+            .map(|(table_day_id, people_ids)| (*table_day_id, people_ids.as_slice())),
+    );
+    let total = tables.iter().map(|t| t.value).sum::<f64>()
+        - unassigned.iter().map(|(_, penalty)| penalty).sum::<f64>()
+        - repeated_neighbours_penalty;
+
+    Explanation {
+        tables,
+        unassigned,
+        repeated_neighbours_penalty,
+        total,
+        person_names: input
+            .people
+            .iter()
+            .filter_map(|p| Some((p.id, p.name.clone()?)))
+            .collect(),
+    }
+}
+
+impl Explanation {
+    fn person_label(&self, person_id: PersonId) -> String {
+        match self.person_names.get(&person_id) {
+            Some(name) => format!("{} ({})", person_id, name),
+            None => person_id.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Objective value {:.3}", self.total)?;
+        for table in self.tables.iter().filter(|t| !t.people.is_empty()) {
+            let table_name = match &table.name {
+                Some(name) => format!("{} {}", table.table_id, name),
+                None => table.table_id.to_string(),
+            };
+            writeln!(
+                f,
+                "Table day {} (table {} on {}, {:?}): {:.3}",
+                table.table_day_id, table_name, table.date, table.layout, table.value
+            )?;
+            for (person_id, [recency, new_table, new_weekday]) in table.people.iter() {
+                writeln!(
+                    f,
+                    "  person {}: recency {:.3}, new table {:.1}, new weekday {:.1}",
+                    self.person_label(*person_id),
+                    recency,
+                    new_table,
+                    new_weekday
+                )?;
+            }
+            for (person_1_id, person_2_id, score) in table.neighbours.iter() {
+                writeln!(
+                    f,
+                    "  neighbours {} and {}: {:.3}",
+                    self.person_label(*person_1_id),
+                    self.person_label(*person_2_id),
+                    score
+                )?;
+            }
+        }
+        for (person_id, penalty) in self.unassigned.iter() {
+            writeln!(
+                f,
+                "Unassigned person {}: -{:.3}",
+                self.person_label(*person_id),
+                penalty
+            )?;
+        }
+        if self.repeated_neighbours_penalty != 0.0 {
+            writeln!(
+                f,
+                "Repeated neighbours: -{:.3}",
+                self.repeated_neighbours_penalty
+            )?;
+        }
+        Ok(())
+    }
+}
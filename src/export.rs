@@ -0,0 +1,286 @@
+//! Seating plans for event staff: every table with its people in seat order, grouped by date
+
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::fmt::Write;
+use std::iter;
+
+use chrono::NaiveDate;
+use itertools::Itertools;
+
+use crate::problem::{Person, PersonId, ProblemDescription, Solution, TableDay, TableLayout};
+
+pub const EXPORT_FORMATS: [&str; 3] = ["csv", "markdown", "html"];
+
+pub fn export_by_name(
+    format: &str,
+    input: &ProblemDescription,
+    solution: &Solution,
+) -> Option<String> {
+    match format {
+        "csv" => Some(csv_export(input, solution)),
+        "markdown" => Some(markdown_export(input, solution)),
+        "html" => Some(html_export(input, solution)),
+        _ => None,
+    }
+}
+
+/// Tables seated on one date with their people in seat order
+type DateTables<'a> = (NaiveDate, Vec<(&'a TableDay, &'a [PersonId])>);
+
+/// Tables with at least one person by date, ordered by table id on each date
+fn seated_tables_by_date<'a>(
+    input: &'a ProblemDescription,
+    solution: &'a Solution,
+) -> Vec<DateTables<'a>> {
+    input
+        .tables
+        .iter()
+        .filter_map(|table| {
+            let people = solution.solution_per_table.get(&table.id)?;
+            (!people.is_empty()).then_some((table, people.as_slice()))
+        })
+        .sorted_by_key(|(table, _)| (table.date, table.table_id, table.id))
+        .chunk_by(|(table, _)| table.date.date_naive())
+        .into_iter()
+        .map(|(date, tables)| (date, tables.collect()))
+        .collect()
+}
+
+/// Names of the people, ids of the ones without a name
+struct PersonLabels<'a>(HashMap<PersonId, &'a Person>);
+
+impl<'a> PersonLabels<'a> {
+    fn new(input: &'a ProblemDescription) -> Self {
+        Self(input.people.iter().map(|p| (p.id, p)).collect())
+    }
+
+    fn person(&self, person_id: PersonId) -> Option<&'a Person> {
+        self.0.get(&person_id).copied()
+    }
+
+    fn label(&self, person_id: PersonId) -> String {
+        self.person(person_id)
+            .and_then(|p| p.name.clone())
+            .unwrap_or_else(|| format!("Person {}", person_id))
+    }
+}
+
+fn table_label(table: &TableDay) -> String {
+    table
+        .name
+        .clone()
+        .unwrap_or_else(|| format!("Table {}", table.table_id))
+}
+
+fn layout_name(layout: TableLayout) -> &'static str {
+    match layout {
+        TableLayout::Ring => "ring",
+        TableLayout::AllPairs => "all_pairs",
+        TableLayout::Bench => "bench",
+    }
+}
+
+/// One row per seat, people without a seat last with the table fields empty
+pub fn csv_export(input: &ProblemDescription, solution: &Solution) -> String {
+    let labels = PersonLabels::new(input);
+    let person_fields = |person_id: PersonId| {
+        let person = labels.person(person_id);
+        [
+            person_id.to_string(),
+            labels.label(person_id),
+            person.and_then(|p| p.email.clone()).unwrap_or_default(),
+            person.map(|p| p.tags.join(";")).unwrap_or_default(),
+        ]
+    };
+    let mut writer = csv::Writer::from_writer(vec![]);
+    writer
+        .write_record([
+            "date",
+            "table_id",
+            "table_day_id",
+            "table",
+            "layout",
+            "seat",
+            "person_id",
+            "person",
+            "email",
+            "tags",
+        ])
+        .unwrap();
+    for (date, tables) in seated_tables_by_date(input, solution) {
+        for (table, people) in tables {
+            for (seat, person_id) in people.iter().enumerate() {
+                writer
+                    .write_record(
+                        [
+                            date.to_string(),
+                            table.table_id.to_string(),
+                            table.id.to_string(),
+                            table_label(table),
+                            layout_name(table.layout).to_string(),
+                            (seat + 1).to_string(),
+                        ]
+                        .into_iter()
+                        .chain(person_fields(*person_id)),
+                    )
+                    .unwrap();
+            }
+        }
+    }
+    for person_id in solution.unassigned.iter() {
+        writer
+            .write_record(iter::repeat_n(String::new(), 6).chain(person_fields(*person_id)))
+            .unwrap();
+    }
+    String::from_utf8(writer.into_inner().unwrap()).unwrap()
+}
+
+pub fn markdown_export(input: &ProblemDescription, solution: &Solution) -> String {
+    let labels = PersonLabels::new(input);
+    let mut result = String::from("# Seating plan\n");
+    for (date, tables) in seated_tables_by_date(input, solution) {
+        write!(result, "\n## {}\n", date).unwrap();
+        for (table, people) in tables {
+            write!(
+                result,
+                "\n### {} ({})\n\n",
+                escape_markdown(&table_label(table)),
+                layout_name(table.layout)
+            )
+            .unwrap();
+            for (seat, person_id) in people.iter().enumerate() {
+                writeln!(
+                    result,
+                    "{}. {}",
+                    seat + 1,
+                    escape_markdown(&labels.label(*person_id))
+                )
+                .unwrap();
+            }
+        }
+    }
+    if !solution.unassigned.is_empty() {
+        result.push_str("\n## Without a seat\n\n");
+        for person_id in solution.unassigned.iter() {
+            writeln!(result, "- {}", escape_markdown(&labels.label(*person_id))).unwrap();
+        }
+    }
+    result
+}
+
+/// Self-contained page to print, round tables are drawn with people around them in seat
+/// order, benches with the seats alternating between the two sides
+pub fn html_export(input: &ProblemDescription, solution: &Solution) -> String {
+    let labels = PersonLabels::new(input);
+    let mut result = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Seating plan</title>\n\
+         <style>\n\
+         body { font-family: sans-serif; }\n\
+         section { page-break-after: always; }\n\
+         figure { display: inline-block; margin: 1em; text-align: center; }\n\
+         svg text { font-size: 11px; }\n\
+         .table { fill: #f4efe6; stroke: #8a7a5c; }\n\
+         </style>\n</head>\n<body>\n<h1>Seating plan</h1>\n",
+    );
+    for (date, tables) in seated_tables_by_date(input, solution) {
+        write!(result, "<section>\n<h2>{}</h2>\n", date).unwrap();
+        for (table, people) in tables {
+            write!(
+                result,
+                "<figure>\n{}<figcaption>{}</figcaption>\n</figure>\n",
+                table_svg(
+                    table.layout,
+                    &people.iter().map(|p| labels.label(*p)).collect_vec()
+                ),
+                escape_html(&table_label(table))
+            )
+            .unwrap();
+        }
+        result.push_str("</section>\n");
+    }
+    if !solution.unassigned.is_empty() {
+        result.push_str("<section>\n<h2>Without a seat</h2>\n<ul>\n");
+        for person_id in solution.unassigned.iter() {
+            writeln!(
+                result,
+                "<li>{}</li>",
+                escape_html(&labels.label(*person_id))
+            )
+            .unwrap();
+        }
+        result.push_str("</ul>\n</section>\n");
+    }
+    result.push_str("</body>\n</html>\n");
+    result
+}
+
+/// Labels of the people in seat order
+fn table_svg(layout: TableLayout, people: &[String]) -> String {
+    let mut result = String::new();
+    match layout {
+        TableLayout::Ring | TableLayout::AllPairs => {
+            let (size, center, table_radius, seat_radius) = (260.0, 130.0, 50.0, 90.0);
+            writeln!(
+                result,
+                "<svg width=\"{size}\" height=\"{size}\" viewBox=\"0 0 {size} {size}\">\n\
+                 <circle class=\"table\" cx=\"{center}\" cy=\"{center}\" r=\"{table_radius}\"/>"
+            )
+            .unwrap();
+            for (seat, label) in people.iter().enumerate() {
+                // First seat at the top, the rest clockwise
+                let angle = 2.0 * PI * seat as f64 / people.len() as f64 - PI / 2.0;
+                write_seat_label(
+                    &mut result,
+                    center + seat_radius * angle.cos(),
+                    center + seat_radius * angle.sin(),
+                    label,
+                );
+            }
+        }
+        TableLayout::Bench => {
+            let seat_width = 90.0;
+            let width = seat_width * people.len().div_ceil(2).max(1) as f64;
+            writeln!(
+                result,
+                "<svg width=\"{width}\" height=\"140\" viewBox=\"0 0 {width} 140\">\n\
+                 <rect class=\"table\" x=\"5\" y=\"45\" width=\"{}\" height=\"50\"/>",
+                width - 10.0
+            )
+            .unwrap();
+            for (seat, label) in people.iter().enumerate() {
+                // Even seats along the top, each odd seat opposite the one before
+                let y = if seat % 2 == 0 { 30.0 } else { 120.0 };
+                let x = seat_width * ((seat / 2) as f64 + 0.5);
+                write_seat_label(&mut result, x, y, label);
+            }
+        }
+    }
+    result.push_str("</svg>\n");
+    result
+}
+
+fn write_seat_label(result: &mut String, x: f64, y: f64, label: &str) {
+    writeln!(
+        result,
+        "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\">{}</text>",
+        x,
+        y,
+        escape_html(label)
+    )
+    .unwrap();
+}
+
+/// A name has to stay on its line and must not end a table cell
+fn escape_markdown(text: &str) -> String {
+    text.replace('|', "\\|")
+        .replace("\r\n", " ")
+        .replace(['\r', '\n'], " ")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
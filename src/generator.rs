@@ -1,35 +1,122 @@
-use crate::problem::{Person, PersonVisit, ProblemDescription, TableDay, MAX_PEOPLE_FOR_TABLE};
-use chrono::{Duration, TimeZone, Utc};
+use std::collections::{BTreeMap, BTreeSet};
+use std::iter;
+
+pub mod manifest;
+pub mod metrics;
+pub mod planted;
+pub mod profile;
+
+use crate::generator::profile::{GeneratorProfile, RelationProfile};
+use crate::problem::{
+    Person, PersonId, PersonVisit, ProblemDescription, RelationSemantics, TableDay, TableLayout,
+    MAX_PEOPLE_FOR_TABLE,
+};
+use chrono::{Duration, NaiveDate, NaiveTime, TimeZone, Utc};
 use itertools::{iproduct, Itertools};
 use rand::prelude::SliceRandom;
 use rand::{thread_rng, Rng};
 
 pub fn generate_problem(no_of_people: usize, no_of_table_days: usize) -> ProblemDescription {
-    let start_date = Utc.with_ymd_and_hms(2024, 3, 3, 0, 0, 0).unwrap();
+    generate_problem_with_rng(no_of_people, no_of_table_days, &mut thread_rng())
+}
+
+/// Same as generate_problem, but with all randomness taken from the given generator,
+/// so a seeded generator gives the same problem every time
+pub fn generate_problem_with_rng(
+    no_of_people: usize,
+    no_of_table_days: usize,
+    rng: &mut impl Rng,
+) -> ProblemDescription {
+    generate_problem_with_profile(
+        &GeneratorProfile::default(),
+        no_of_people,
+        no_of_table_days,
+        rng,
+    )
+}
+
+pub fn generate_problem_with_profile(
+    profile: &GeneratorProfile,
+    no_of_people: usize,
+    no_of_table_days: usize,
+    rng: &mut impl Rng,
+) -> ProblemDescription {
+    generate_problem_with_groups(profile, no_of_people, no_of_table_days, rng).0
+}
+
+/// Same as generate_problem_with_profile, also returning the groups the relations were planted
+/// in, or None when the relation model draws every pair independently
+pub fn generate_problem_with_groups(
+    profile: &GeneratorProfile,
+    no_of_people: usize,
+    no_of_table_days: usize,
+    rng: &mut impl Rng,
+) -> (ProblemDescription, Option<Vec<Vec<PersonId>>>) {
+    let start_date = Utc.from_utc_datetime(&profile.start_date.and_time(NaiveTime::MIN));
 
-    // no of tables
-    let no_of_tables = (no_of_people / MAX_PEOPLE_FOR_TABLE / 2).max(3);
+    // no of tables, enough for every table day to fit on the days of the profile
+    let no_of_days = profile.no_of_days.max(1);
+    let no_of_tables = no_of_table_days.div_ceil(no_of_days).max(3);
 
-    let days = (0..7).map(|day| start_date + Duration::days((day + 1) as i64));
+    let days = (0..no_of_days).map(|day| start_date + Duration::days((day + 1) as i64));
     let mut all_possible_day_tables = iproduct!(days, 0..no_of_tables).collect_vec();
-    all_possible_day_tables.shuffle(&mut thread_rng());
-
-    let problem_data = ProblemDescription {
-        tables: all_possible_day_tables
-            .into_iter()
-            .enumerate()
-            .map(|(id, (date, table_id))| TableDay { id, table_id, date })
-            .take(no_of_table_days)
-            .collect(),
+    all_possible_day_tables.shuffle(rng);
+
+    let tables: Vec<TableDay> = all_possible_day_tables
+        .into_iter()
+        .enumerate()
+        .map(|(id, (date, table_id))| TableDay {
+            id,
+            table_id,
+            date,
+            layout: TableLayout::Ring,
+            name: None,
+            tags: vec![],
+            metadata: BTreeMap::new(),
+        })
+        .take(no_of_table_days)
+        .collect();
+
+    // Each person gets a date with a free seat guaranteed, so everybody can always be seated
+    let mut seat_dates = tables
+        .iter()
+        .flat_map(|t| iter::repeat_n(t.date.date_naive(), MAX_PEOPLE_FOR_TABLE))
+        .collect_vec();
+    seat_dates.shuffle(rng);
+    let event_dates: BTreeSet<NaiveDate> = seat_dates.iter().cloned().collect();
+
+    let groups = generate_groups(&profile.relations, no_of_people, rng);
+    let mut group_of_person = vec![0; no_of_people];
+    for (group_index, group) in groups.iter().flatten().enumerate() {
+        for person_id in group {
+            group_of_person[*person_id] = group_index;
+        }
+    }
+
+    let problem = ProblemDescription {
+        tables,
         people: (0..no_of_people)
             .map(|id| Person {
                 id,
-                visits: (0..thread_rng().gen_range(0..6))
+                visits: (0..generate_no_of_visits(profile, rng))
                     .map(|_| PersonVisit {
-                        table_id: thread_rng().gen_range(0..no_of_tables),
-                        at: start_date - Duration::days(thread_rng().gen_range(1..30)),
+                        table_id: rng.gen_range(0..no_of_tables),
+                        at: start_date
+                            - Duration::days(rng.gen_range(1..=profile.max_days_since_visit)),
                     })
                     .collect(),
+                available_dates: generate_availability(
+                    profile,
+                    seat_dates.get(id),
+                    &event_dates,
+                    rng,
+                ),
+                unassigned_penalty: None,
+                required_seatings: 1,
+                name: None,
+                email: None,
+                tags: vec![],
+                metadata: BTreeMap::new(),
             })
             .collect(),
         people_relations: (0..no_of_people)
@@ -37,17 +124,125 @@ pub fn generate_problem(no_of_people: usize, no_of_table_days: usize) -> Problem
                 (
                     p1,
                     ((p1 + 1)..no_of_people)
-                        .filter_map(move |p2| {
-                            if thread_rng().gen_range(0.0..1.0) > 0.3 {
-                                Some((p2, thread_rng().gen_range(0.0..2.0)))
-                            } else {
-                                None
-                            }
+                        .filter_map(|p2| {
+                            let same_group = group_of_person[p1] == group_of_person[p2];
+                            generate_relation(&profile.relations, same_group, rng)
+                                .map(|score| (p2, score))
                         })
                         .collect(),
                 )
             })
             .collect(),
+        relation_semantics: RelationSemantics::Symmetric,
     };
-    problem_data
+    (problem, groups)
+}
+
+fn generate_no_of_visits(profile: &GeneratorProfile, rng: &mut impl Rng) -> usize {
+    if rng.gen_bool(profile.new_joiner_share) {
+        0
+    } else {
+        rng.gen_range(profile.min_visits..=profile.max_visits)
+    }
+}
+
+/// Consecutive ids for teams, shuffled people for communities
+fn generate_groups(
+    relations: &RelationProfile,
+    no_of_people: usize,
+    rng: &mut impl Rng,
+) -> Option<Vec<Vec<PersonId>>> {
+    match relations {
+        RelationProfile::Uniform { .. } => None,
+        RelationProfile::Teams { team_size, .. } => Some(
+            (0..no_of_people)
+                .chunks((*team_size).max(1))
+                .into_iter()
+                .map(|team| team.collect())
+                .collect(),
+        ),
+        RelationProfile::Communities {
+            min_group_size,
+            max_group_size,
+            ..
+        } => {
+            let mut people = (0..no_of_people).collect_vec();
+            people.shuffle(rng);
+            let mut people = people.into_iter().peekable();
+            let mut groups = vec![];
+            while people.peek().is_some() {
+                let group_size = rng.gen_range((*min_group_size).max(1)..=*max_group_size);
+                groups.push(people.by_ref().take(group_size).collect());
+            }
+            Some(groups)
+        }
+    }
+}
+
+fn generate_relation(
+    relations: &RelationProfile,
+    same_group: bool,
+    rng: &mut impl Rng,
+) -> Option<f64> {
+    let (density, min_score, max_score) = match relations {
+        RelationProfile::Uniform {
+            density,
+            min_score,
+            max_score,
+        } => (*density, *min_score, *max_score),
+        RelationProfile::Teams {
+            density_within,
+            density_between,
+            min_score,
+            max_score,
+            ..
+        } => {
+            if same_group {
+                (*density_within, *min_score, *max_score)
+            } else {
+                (*density_between, *min_score, *max_score)
+            }
+        }
+        RelationProfile::Communities {
+            density_within,
+            density_between,
+            min_score,
+            max_score,
+            min_conflict_score,
+            max_conflict_score,
+            ..
+        } => {
+            if same_group {
+                (*density_within, *min_score, *max_score)
+            } else {
+                (*density_between, *min_conflict_score, *max_conflict_score)
+            }
+        }
+    };
+    if rng.gen_bool(density) {
+        Some(rng.gen_range(min_score..=max_score))
+    } else {
+        None
+    }
+}
+
+/// Most people attend the whole event, the rest register for their guaranteed date
+/// and some of the other event dates
+fn generate_availability(
+    profile: &GeneratorProfile,
+    guaranteed_date: Option<&NaiveDate>,
+    event_dates: &BTreeSet<NaiveDate>,
+    rng: &mut impl Rng,
+) -> Option<BTreeSet<NaiveDate>> {
+    let guaranteed_date = guaranteed_date?;
+    if rng.gen_bool(profile.fully_available_share) {
+        return None;
+    }
+    Some(
+        event_dates
+            .iter()
+            .filter(|date| *date == guaranteed_date || rng.gen_bool(profile.other_date_probability))
+            .cloned()
+            .collect(),
+    )
 }
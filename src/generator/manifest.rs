@@ -0,0 +1,148 @@
+use std::error::Error;
+use std::path::Path;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::generator::generate_problem_with_groups;
+use crate::generator::planted::generate_planted_problem;
+use crate::generator::profile::GeneratorProfile;
+use crate::schema::problem_to_json;
+
+pub const SIZE_PRESET_NAMES: [&str; 4] = ["small", "medium", "large", "huge"];
+
+/// Range of the number of people for a size preset, end exclusive
+pub fn size_preset(name: &str) -> Option<(usize, usize)> {
+    match name {
+        "small" => Some((10, 30)),
+        "medium" => Some((100, 200)),
+        "large" => Some((400, 800)),
+        "huge" => Some((1500, 3000)),
+        _ => None,
+    }
+}
+
+/// What to generate, written into the manifest so every problem can be generated again
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenerateSettings {
+    pub no_of_problems: usize,
+    pub min_people: usize,
+    /// Exclusive
+    pub max_people: usize,
+    /// Same count for every problem, derived from the capacity slack of the profile if not set
+    pub no_of_table_days: Option<usize>,
+    /// Seed of the generator drawing the seed of each problem
+    pub seed: u64,
+    /// Write the planted groups of the relation model next to each problem
+    pub groups: bool,
+    /// Build each problem around a hidden optimal seating, see planted
+    pub planted: bool,
+    pub profile: GeneratorProfile,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub settings: GenerateSettings,
+    pub problems: Vec<ManifestEntry>,
+}
+
+/// Generated problem, file names are relative to the manifest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub file: String,
+    /// Generating with this seed and the settings gives the same problem
+    pub seed: u64,
+    pub no_of_people: usize,
+    pub no_of_table_days: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub groups_file: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub optimum_file: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub optimal_value: Option<f64>,
+}
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Writes the problems and a manifest listing them into the output dir
+pub fn generate_files(
+    settings: &GenerateSettings,
+    output_dir: &Path,
+) -> Result<Manifest, Box<dyn Error>> {
+    if settings.min_people >= settings.max_people {
+        return Err("Minimum number of people must be below the maximum".into());
+    }
+    std::fs::create_dir_all(output_dir)?;
+
+    let mut seeds = StdRng::seed_from_u64(settings.seed);
+    let mut problems = vec![];
+    for problem_id in 0..settings.no_of_problems {
+        let seed = seeds.gen();
+        let mut rng = StdRng::seed_from_u64(seed);
+        let no_of_people = rng.gen_range(settings.min_people..settings.max_people);
+        let no_of_table_days = settings
+            .no_of_table_days
+            .unwrap_or_else(|| settings.profile.no_of_table_days(no_of_people));
+
+        println!(
+            "Generating problem {} no_of_people {} no_of_table_days {}",
+            problem_id, no_of_people, no_of_table_days
+        );
+        let mut entry = ManifestEntry {
+            file: format!("p_{}.json", problem_id),
+            seed,
+            no_of_people,
+            no_of_table_days,
+            groups_file: None,
+            optimum_file: None,
+            optimal_value: None,
+        };
+        let problem = if settings.planted {
+            let (problem, optimum) = generate_planted_problem(
+                &settings.profile,
+                no_of_people,
+                no_of_table_days,
+                &mut rng,
+            );
+            let optimum_file = format!("p_{}.optimum.json", problem_id);
+            std::fs::write(
+                output_dir.join(&optimum_file),
+                serde_json::to_string(&optimum)?,
+            )?;
+            entry.optimum_file = Some(optimum_file);
+            entry.optimal_value = Some(optimum.optimal_value);
+            problem
+        } else {
+            let (problem, groups) = generate_problem_with_groups(
+                &settings.profile,
+                no_of_people,
+                no_of_table_days,
+                &mut rng,
+            );
+            if let Some(groups) = groups.filter(|_| settings.groups) {
+                let groups_file = format!("p_{}.groups.json", problem_id);
+                std::fs::write(
+                    output_dir.join(&groups_file),
+                    serde_json::to_string(&groups)?,
+                )?;
+                entry.groups_file = Some(groups_file);
+            }
+            problem
+        };
+        // What was generated, planted tables may need more table days than asked for
+        entry.no_of_table_days = problem.tables.len();
+        std::fs::write(output_dir.join(&entry.file), problem_to_json(&problem)?)?;
+        problems.push(entry);
+    }
+
+    let manifest = Manifest {
+        settings: settings.clone(),
+        problems,
+    };
+    std::fs::write(
+        output_dir.join(MANIFEST_FILE),
+        serde_json::to_string_pretty(&manifest)?,
+    )?;
+    Ok(manifest)
+}
@@ -0,0 +1,27 @@
+//! How well a solution recovers the groups relations were planted in
+
+use std::collections::HashMap;
+
+use itertools::Itertools;
+
+use crate::problem::{PersonId, Solution};
+
+/// Share of the pairs seated at the same table that belong to the same planted group,
+/// None when no table has two people
+pub fn same_group_pair_share(groups: &[Vec<PersonId>], solution: &Solution) -> Option<f64> {
+    let group_of_person: HashMap<PersonId, usize> = groups
+        .iter()
+        .enumerate()
+        .flat_map(|(group_index, group)| group.iter().map(move |p| (*p, group_index)))
+        .collect();
+    let (no_of_pairs, no_of_same_group_pairs) = solution
+        .solution_per_table
+        .values()
+        .flat_map(|people| people.iter().tuple_combinations())
+        .fold((0, 0), |(pairs, same_group), (p1, p2)| {
+            let is_same_group = group_of_person.contains_key(p1)
+                && group_of_person.get(p1) == group_of_person.get(p2);
+            (pairs + 1, same_group + is_same_group as usize)
+        });
+    (no_of_pairs > 0).then(|| no_of_same_group_pairs as f64 / no_of_pairs as f64)
+}
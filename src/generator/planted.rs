@@ -0,0 +1,204 @@
+//! Problems built around a hidden seating that is known to be optimal.
+//!
+//! A seated person scores at most 2 on their own: 1 for the last visit being 30 or more days
+//! ago, 0.5 for a new table and 0.5 for a new weekday. On a ring every person has at most two
+//! neighbour incidences, a table of two counts the pair twice, so with no relation above
+//! PLANTED_RELATION_SCORE nobody gets more than twice that from neighbours. The planted seating
+//! reaches both bounds for everybody: people only have the positive relation with their
+//! planted neighbours, all visits are at least 30 days old and never at the planted table or
+//! on the planted weekday. Conflicts are only added between people who are not planted
+//! neighbours, so they can only lower the value of other seatings.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use chrono::{Datelike, Duration, NaiveTime, TimeZone, Utc};
+use itertools::{iproduct, Itertools};
+use rand::prelude::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::generator::profile::GeneratorProfile;
+use crate::objective_value_calculator::v1::ObjectiveValueCalculator;
+use crate::problem::{
+    Person, PersonId, PersonVisit, ProblemDescription, RelationSemantics, Solution, TableDay,
+    TableLayout, MAX_PEOPLE_FOR_TABLE,
+};
+
+/// Relation between planted neighbours, the highest relation in the problem
+pub const PLANTED_RELATION_SCORE: f64 = 1.0;
+/// Chance that two people who are not planted neighbours are in conflict
+const CONFLICT_DENSITY: f64 = 0.05;
+/// Visits this long before a table day give the full recency score
+const MIN_DAYS_SINCE_VISIT: i64 = 30;
+
+/// Seating a planted problem was built around, no seating of the problem is worth more
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PlantedOptimum {
+    pub optimal_value: f64,
+    pub solution: Solution,
+}
+
+/// Uses the dates, visit counts and availability of the profile, its relation model is replaced
+/// by the planted one. There are at least as many table days as planted tables, the ones beyond
+/// stay empty in the optimum.
+pub fn generate_planted_problem(
+    profile: &GeneratorProfile,
+    no_of_people: usize,
+    no_of_table_days: usize,
+    rng: &mut impl Rng,
+) -> (ProblemDescription, PlantedOptimum) {
+    let start_date = Utc.from_utc_datetime(&profile.start_date.and_time(NaiveTime::MIN));
+
+    let mut people_ids = (0..no_of_people).collect_vec();
+    people_ids.shuffle(rng);
+    let planted_tables = planted_table_sizes(no_of_people, rng)
+        .into_iter()
+        .scan(people_ids.into_iter(), |people_ids, size| {
+            Some(people_ids.by_ref().take(size).collect_vec())
+        })
+        .collect_vec();
+
+    let no_of_table_days = no_of_table_days.max(planted_tables.len());
+    let no_of_days = profile.no_of_days.max(1);
+    let no_of_tables = no_of_table_days.div_ceil(no_of_days).max(3);
+    let days = (0..no_of_days).map(|day| start_date + Duration::days((day + 1) as i64));
+    let mut all_possible_day_tables = iproduct!(days, 0..no_of_tables).collect_vec();
+    all_possible_day_tables.shuffle(rng);
+    let tables: Vec<TableDay> = all_possible_day_tables
+        .into_iter()
+        .enumerate()
+        .map(|(id, (date, table_id))| TableDay {
+            id,
+            table_id,
+            date,
+            layout: TableLayout::Ring,
+            name: None,
+            tags: vec![],
+            metadata: BTreeMap::new(),
+        })
+        .take(no_of_table_days)
+        .collect();
+    let event_dates: BTreeSet<_> = tables.iter().map(|t| t.date.date_naive()).collect();
+
+    let mut planted_table_of_person = vec![0; no_of_people];
+    let mut planted_neighbours = HashSet::new();
+    for (table_day_id, people) in planted_tables.iter().enumerate() {
+        for (seat, person_id) in people.iter().enumerate() {
+            planted_table_of_person[*person_id] = table_day_id;
+            if people.len() > 1 {
+                let neighbour_id = people[(seat + 1) % people.len()];
+                planted_neighbours
+                    .insert((*person_id.min(&neighbour_id), *person_id.max(&neighbour_id)));
+            }
+        }
+    }
+
+    let people = (0..no_of_people)
+        .map(|id| {
+            let planted_table = &tables[planted_table_of_person[id]];
+            let no_of_visits = if rng.gen_bool(profile.new_joiner_share) {
+                0
+            } else {
+                rng.gen_range(profile.min_visits..=profile.max_visits)
+            };
+            let visits = (0..no_of_visits)
+                .map(|_| {
+                    let mut at = start_date
+                        - Duration::days(rng.gen_range(
+                            MIN_DAYS_SINCE_VISIT
+                                ..=MIN_DAYS_SINCE_VISIT.max(profile.max_days_since_visit),
+                        ));
+                    if at.weekday() == planted_table.date.weekday() {
+                        at -= Duration::days(1);
+                    }
+                    // Any table but the planted one
+                    let mut table_id = rng.gen_range(0..no_of_tables - 1);
+                    if table_id >= planted_table.table_id {
+                        table_id += 1;
+                    }
+                    PersonVisit { table_id, at }
+                })
+                .collect();
+            let available_dates = if rng.gen_bool(profile.fully_available_share) {
+                None
+            } else {
+                Some(
+                    event_dates
+                        .iter()
+                        .filter(|date| {
+                            **date == planted_table.date.date_naive()
+                                || rng.gen_bool(profile.other_date_probability)
+                        })
+                        .cloned()
+                        .collect(),
+                )
+            };
+            Person {
+                id,
+                visits,
+                available_dates,
+                unassigned_penalty: None,
+                required_seatings: 1,
+                name: None,
+                email: None,
+                tags: vec![],
+                metadata: BTreeMap::new(),
+            }
+        })
+        .collect();
+
+    let mut people_relations: BTreeMap<PersonId, BTreeMap<PersonId, f64>> = BTreeMap::new();
+    for (p1, p2) in (0..no_of_people).tuple_combinations() {
+        let score = if planted_neighbours.contains(&(p1, p2)) {
+            PLANTED_RELATION_SCORE
+        } else if rng.gen_bool(CONFLICT_DENSITY) {
+            rng.gen_range(-2.0..=-0.1)
+        } else {
+            continue;
+        };
+        people_relations.entry(p1).or_default().insert(p2, score);
+    }
+
+    let problem = ProblemDescription {
+        people,
+        tables,
+        people_relations,
+        relation_semantics: RelationSemantics::Symmetric,
+    };
+    let solution = Solution {
+        solution_per_table: problem
+            .tables
+            .iter()
+            .map(|t| (t.id, planted_tables.get(t.id).cloned().unwrap_or_default()))
+            .collect(),
+        unassigned: vec![],
+    };
+    let optimal_value = ObjectiveValueCalculator::new(&problem).solution_value(&solution);
+    (
+        problem,
+        PlantedOptimum {
+            optimal_value,
+            solution,
+        },
+    )
+}
+
+/// Sizes of at least 2 adding up to the number of people, unless there is only one person
+fn planted_table_sizes(no_of_people: usize, rng: &mut impl Rng) -> Vec<usize> {
+    let mut sizes = vec![];
+    let mut remaining = no_of_people;
+    while remaining > 0 {
+        let mut size = rng.gen_range(2..=MAX_PEOPLE_FOR_TABLE).min(remaining);
+        // Never leave a single person for the last table
+        if remaining - size == 1 {
+            if size < MAX_PEOPLE_FOR_TABLE {
+                size += 1;
+            } else {
+                size -= 1;
+            }
+        }
+        sizes.push(size);
+        remaining -= size;
+    }
+    sizes
+}
@@ -0,0 +1,242 @@
+use std::error::Error;
+use std::path::Path;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::problem::MAX_PEOPLE_FOR_TABLE;
+
+pub const PROFILE_NAMES: [&str; 7] = [
+    "default",
+    "sparse_social",
+    "dense_teams",
+    "communities",
+    "heavy_history",
+    "new_joiners",
+    "tight_capacity",
+];
+
+/// Distributions the generator draws problems from. Fields missing in a spec file
+/// keep the default profile values.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GeneratorProfile {
+    /// Event dates start the day after
+    pub start_date: NaiveDate,
+    pub no_of_days: usize,
+    /// Extra seats over one per person as a share of the needed seats, 0 fills every table
+    pub capacity_slack: f64,
+    /// Past visits of a person who is not new, both ends inclusive
+    pub min_visits: usize,
+    pub max_visits: usize,
+    /// Past visits happened up to this many days before the start date
+    pub max_days_since_visit: i64,
+    /// Share of people without any past visits
+    pub new_joiner_share: f64,
+    /// Share of people who can be seated on any date
+    pub fully_available_share: f64,
+    /// Chance that a person who is not fully available registers for each other date
+    pub other_date_probability: f64,
+    pub relations: RelationProfile,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "model", rename_all = "snake_case", deny_unknown_fields)]
+pub enum RelationProfile {
+    /// Every pair independently
+    Uniform {
+        density: f64,
+        min_score: f64,
+        max_score: f64,
+    },
+    /// People split into consecutive teams, pairs within a team are much more likely related
+    Teams {
+        team_size: usize,
+        density_within: f64,
+        density_between: f64,
+        min_score: f64,
+        max_score: f64,
+    },
+    /// Stochastic block model: people are shuffled into groups of random size, pairs within a
+    /// group like each other and pairs across groups may be in conflict
+    Communities {
+        min_group_size: usize,
+        max_group_size: usize,
+        density_within: f64,
+        density_between: f64,
+        min_score: f64,
+        max_score: f64,
+        /// Scores across groups, negative for conflicts
+        min_conflict_score: f64,
+        max_conflict_score: f64,
+    },
+}
+
+impl Default for GeneratorProfile {
+    fn default() -> Self {
+        Self {
+            start_date: NaiveDate::from_ymd_opt(2024, 3, 3).unwrap(),
+            no_of_days: 7,
+            capacity_slack: 1.05,
+            min_visits: 0,
+            max_visits: 5,
+            max_days_since_visit: 29,
+            new_joiner_share: 0.0,
+            fully_available_share: 0.6,
+            other_date_probability: 0.3,
+            relations: RelationProfile::Uniform {
+                density: 0.7,
+                min_score: 0.0,
+                max_score: 2.0,
+            },
+        }
+    }
+}
+
+impl GeneratorProfile {
+    /// Built-in profiles, see PROFILE_NAMES
+    pub fn named(name: &str) -> Option<Self> {
+        let default = Self::default();
+        match name {
+            "default" => Some(default),
+            "sparse_social" => Some(Self {
+                relations: RelationProfile::Uniform {
+                    density: 0.05,
+                    min_score: 0.0,
+                    max_score: 2.0,
+                },
+                ..default
+            }),
+            "dense_teams" => Some(Self {
+                relations: RelationProfile::Teams {
+                    team_size: 8,
+                    density_within: 0.9,
+                    density_between: 0.02,
+                    min_score: 0.5,
+                    max_score: 2.0,
+                },
+                ..default
+            }),
+            "communities" => Some(Self {
+                relations: RelationProfile::Communities {
+                    min_group_size: 3,
+                    max_group_size: 10,
+                    density_within: 0.8,
+                    density_between: 0.05,
+                    min_score: 0.5,
+                    max_score: 2.0,
+                    min_conflict_score: -2.0,
+                    max_conflict_score: -0.5,
+                },
+                ..default
+            }),
+            "heavy_history" => Some(Self {
+                min_visits: 5,
+                max_visits: 20,
+                max_days_since_visit: 90,
+                ..default
+            }),
+            "new_joiners" => Some(Self {
+                new_joiner_share: 0.6,
+                ..default
+            }),
+            "tight_capacity" => Some(Self {
+                capacity_slack: 0.0,
+                ..default
+            }),
+            _ => None,
+        }
+    }
+
+    /// Reads a profile spec, TOML if the file ends with .toml and JSON otherwise
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let content = std::fs::read_to_string(path)?;
+        let profile: Self = if path
+            .extension()
+            .is_some_and(|extension| extension == "toml")
+        {
+            toml::from_str(&content)?
+        } else {
+            serde_json::from_str(&content)?
+        };
+        profile.check()?;
+        Ok(profile)
+    }
+
+    /// Values the generator can not draw from, checked again after overriding any of them
+    pub fn check(&self) -> Result<(), String> {
+        let (ranges, shares) = match &self.relations {
+            RelationProfile::Uniform {
+                density,
+                min_score,
+                max_score,
+            } => (vec![(*min_score, *max_score)], vec![*density]),
+            RelationProfile::Teams {
+                team_size,
+                density_within,
+                density_between,
+                min_score,
+                max_score,
+            } => (
+                vec![(1.0, *team_size as f64), (*min_score, *max_score)],
+                vec![*density_within, *density_between],
+            ),
+            RelationProfile::Communities {
+                min_group_size,
+                max_group_size,
+                density_within,
+                density_between,
+                min_score,
+                max_score,
+                min_conflict_score,
+                max_conflict_score,
+            } => (
+                vec![
+                    (1.0, *min_group_size as f64),
+                    (*min_group_size as f64, *max_group_size as f64),
+                    (*min_score, *max_score),
+                    (*min_conflict_score, *max_conflict_score),
+                ],
+                vec![*density_within, *density_between],
+            ),
+        };
+        let shares = shares
+            .into_iter()
+            .chain([
+                self.new_joiner_share,
+                self.fully_available_share,
+                self.other_date_probability,
+            ])
+            .collect::<Vec<_>>();
+        // NaN passes every comparison below, so it is ruled out first
+        if ranges
+            .iter()
+            .flat_map(|(min, max)| [min, max])
+            .chain(shares.iter())
+            .chain([&self.capacity_slack])
+            .any(|value| !value.is_finite())
+        {
+            return Err("Scores, shares and capacity slack must be finite numbers".to_string());
+        }
+        if shares.iter().any(|share| !(0.0..=1.0).contains(share)) {
+            return Err("Shares, densities and probabilities must be between 0 and 1".to_string());
+        }
+        if self.min_visits > self.max_visits || ranges.iter().any(|(min, max)| min > max) {
+            return Err(
+                "Minimum must not be greater than maximum and groups must not be empty".to_string(),
+            );
+        }
+        if self.no_of_days == 0 || self.max_days_since_visit < 1 || self.capacity_slack < 0.0 {
+            return Err(
+                "Days must be positive and capacity slack must not be negative".to_string(),
+            );
+        }
+        Ok(())
+    }
+
+    /// Table days needed to seat everybody once plus the capacity slack
+    pub fn no_of_table_days(&self, no_of_people: usize) -> usize {
+        let min_no_of_table_days = no_of_people.div_ceil(MAX_PEOPLE_FOR_TABLE);
+        min_no_of_table_days + (min_no_of_table_days as f64 * self.capacity_slack).ceil() as usize
+    }
+}
@@ -0,0 +1,330 @@
+//! Problems from the CSV files HR and venue systems export, one file per kind of row:
+//!
+//! - people.csv: `id`, optional `unassigned_penalty`, `required_seatings`,
+//!   `available_dates`, `name`, `email` and `tags`
+//! - tables.csv: `id`, `table_id`, `date` and optional `layout`, `name` and `tags`
+//! - visits.csv, optional: `person_id`, `table_id` and `at`
+//! - relations.csv, optional: `person_1_id`, `person_2_id` and `score`
+//!
+//! Dates are `YYYY-MM-DD` or RFC 3339 timestamps, lists of dates and tags are separated
+//! by `;`. Columns named `metadata.<key>` of people and tables go into their metadata under
+//! the key. Empty optional fields take the same defaults as in the JSON format.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+
+use crate::problem::{
+    Person, PersonId, PersonVisit, ProblemDescription, RelationSemantics, TableDay, TableLayout,
+};
+
+pub const PEOPLE_FILE: &str = "people.csv";
+pub const TABLES_FILE: &str = "tables.csv";
+pub const VISITS_FILE: &str = "visits.csv";
+pub const RELATIONS_FILE: &str = "relations.csv";
+
+/// What is wrong with which row, line numbers count the header as line 1
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportError {
+    pub file: String,
+    pub line: Option<u64>,
+    pub message: String,
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{} line {}: {}", self.file, line, self.message),
+            None => write!(f, "{}: {}", self.file, self.message),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+/// Reads the CSV files from the dir, visits.csv and relations.csv may be missing
+pub fn import_csv_dir(
+    dir: &Path,
+    relation_semantics: RelationSemantics,
+) -> Result<ProblemDescription, ImportError> {
+    let mut people = read_people(&dir.join(PEOPLE_FILE))?;
+    let tables = read_tables(&dir.join(TABLES_FILE))?;
+    let people_ids: HashMap<PersonId, usize> = people
+        .iter()
+        .enumerate()
+        .map(|(index, p)| (p.id, index))
+        .collect();
+
+    let visits_path = dir.join(VISITS_FILE);
+    if visits_path.exists() {
+        for row in CsvRows::open(&visits_path)? {
+            let row = row?;
+            let person_id: PersonId = row.parse("person_id")?;
+            let index = *people_ids
+                .get(&person_id)
+                .ok_or_else(|| row.error(format!("unknown person {}", person_id)))?;
+            people[index].visits.push(PersonVisit {
+                table_id: row.parse("table_id")?,
+                at: row.parse_with("at", parse_date_time)?,
+            });
+        }
+    }
+
+    let mut people_relations: BTreeMap<PersonId, BTreeMap<PersonId, f64>> = BTreeMap::new();
+    let relations_path = dir.join(RELATIONS_FILE);
+    if relations_path.exists() {
+        for row in CsvRows::open(&relations_path)? {
+            let row = row?;
+            let person_1_id: PersonId = row.parse("person_1_id")?;
+            let person_2_id: PersonId = row.parse("person_2_id")?;
+            for person_id in [person_1_id, person_2_id] {
+                if !people_ids.contains_key(&person_id) {
+                    return Err(row.error(format!("unknown person {}", person_id)));
+                }
+            }
+            let score: f64 = row.parse("score")?;
+            if !score.is_finite() {
+                return Err(row.error("score is not a finite number".to_string()));
+            }
+            people_relations
+                .entry(person_1_id)
+                .or_default()
+                .insert(person_2_id, score);
+        }
+    }
+
+    Ok(ProblemDescription {
+        people,
+        tables,
+        people_relations,
+        relation_semantics,
+    })
+}
+
+fn read_people(path: &Path) -> Result<Vec<Person>, ImportError> {
+    let mut people = vec![];
+    let mut ids = HashSet::new();
+    for row in CsvRows::open(path)? {
+        let row = row?;
+        let id: PersonId = row.parse("id")?;
+        if !ids.insert(id) {
+            return Err(row.error(format!("person {} is defined more than once", id)));
+        }
+        people.push(Person {
+            id,
+            visits: vec![],
+            available_dates: row.parse_optional_with("available_dates", |dates| {
+                dates
+                    .split(';')
+                    .map(|date| {
+                        parse_date(date.trim()).map_err(|e| format!("'{}' is {}", date.trim(), e))
+                    })
+                    .collect::<Result<BTreeSet<_>, _>>()
+            })?,
+            unassigned_penalty: row.parse_optional_with("unassigned_penalty", |penalty| {
+                penalty
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|penalty| penalty.is_finite())
+                    .ok_or_else(|| "not a finite number".to_string())
+            })?,
+            required_seatings: row
+                .parse_optional_with("required_seatings", |seatings| {
+                    seatings.parse().map_err(|e| format!("{}", e))
+                })?
+                .unwrap_or(1),
+            name: row.text("name"),
+            email: row.text("email"),
+            tags: row.list("tags"),
+            metadata: row.metadata(),
+        });
+    }
+    Ok(people)
+}
+
+fn read_tables(path: &Path) -> Result<Vec<TableDay>, ImportError> {
+    let mut tables = vec![];
+    let mut ids = HashSet::new();
+    for row in CsvRows::open(path)? {
+        let row = row?;
+        let id = row.parse("id")?;
+        if !ids.insert(id) {
+            return Err(row.error(format!("table {} is defined more than once", id)));
+        }
+        tables.push(TableDay {
+            id,
+            table_id: row.parse("table_id")?,
+            date: row.parse_with("date", parse_date_time)?,
+            layout: row
+                .parse_optional_with("layout", parse_layout)?
+                .unwrap_or_default(),
+            name: row.text("name"),
+            tags: row.list("tags"),
+            metadata: row.metadata(),
+        });
+    }
+    Ok(tables)
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| "not a YYYY-MM-DD date".to_string())
+}
+
+/// Plain dates are taken as midnight UTC
+fn parse_date_time(value: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(date_time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(date_time.with_timezone(&Utc));
+    }
+    parse_date(value)
+        .map(|date| date.and_time(NaiveTime::MIN).and_utc())
+        .map_err(|_| "not a YYYY-MM-DD date or RFC 3339 timestamp".to_string())
+}
+
+fn parse_layout(value: &str) -> Result<TableLayout, String> {
+    match value {
+        "ring" => Ok(TableLayout::Ring),
+        "all_pairs" => Ok(TableLayout::AllPairs),
+        "bench" => Ok(TableLayout::Bench),
+        _ => Err("not one of ring, all_pairs and bench".to_string()),
+    }
+}
+
+/// Rows of a CSV file with columns looked up by header name
+struct CsvRows {
+    file: String,
+    headers: csv::StringRecord,
+    records: csv::StringRecordsIntoIter<std::fs::File>,
+}
+
+impl CsvRows {
+    fn open(path: &Path) -> Result<Self, ImportError> {
+        let file = path.file_name().map_or_else(
+            || path.display().to_string(),
+            |name| name.to_string_lossy().to_string(),
+        );
+        let error = |message: String| ImportError {
+            file: file.clone(),
+            line: None,
+            message,
+        };
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_path(path)
+            .map_err(|e| error(e.to_string()))?;
+        let headers = reader.headers().map_err(|e| error(e.to_string()))?.clone();
+        Ok(Self {
+            file,
+            headers,
+            records: reader.into_records(),
+        })
+    }
+}
+
+impl Iterator for CsvRows {
+    type Item = Result<CsvRow, ImportError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let record = self.records.next()?;
+        Some(
+            record
+                .map(|record| CsvRow {
+                    file: self.file.clone(),
+                    line: record.position().map_or(0, |p| p.line()),
+                    headers: self.headers.clone(),
+                    record,
+                })
+                .map_err(|e| ImportError {
+                    file: self.file.clone(),
+                    line: e.position().map(|p| p.line()),
+                    message: e.to_string(),
+                }),
+        )
+    }
+}
+
+struct CsvRow {
+    file: String,
+    line: u64,
+    headers: csv::StringRecord,
+    record: csv::StringRecord,
+}
+
+impl CsvRow {
+    fn error(&self, message: String) -> ImportError {
+        ImportError {
+            file: self.file.clone(),
+            line: Some(self.line),
+            message,
+        }
+    }
+
+    /// None for a missing column or an empty field
+    fn field(&self, column: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .position(|header| header == column)
+            .and_then(|index| self.record.get(index))
+            .filter(|value| !value.is_empty())
+    }
+
+    fn text(&self, column: &str) -> Option<String> {
+        self.field(column).map(str::to_string)
+    }
+
+    fn list(&self, column: &str) -> Vec<String> {
+        self.field(column)
+            .map(|values| {
+                values
+                    .split(';')
+                    .map(str::trim)
+                    .filter(|value| !value.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Non-empty fields of the `metadata.<key>` columns by key
+    fn metadata(&self) -> BTreeMap<String, String> {
+        self.headers
+            .iter()
+            .zip(self.record.iter())
+            .filter_map(|(header, value)| {
+                let key = header.strip_prefix("metadata.")?;
+                (!value.is_empty()).then(|| (key.to_string(), value.to_string()))
+            })
+            .collect()
+    }
+
+    fn parse<T: FromStr>(&self, column: &str) -> Result<T, ImportError>
+    where
+        T::Err: fmt::Display,
+    {
+        self.parse_with(column, |value| value.parse().map_err(|e| format!("{}", e)))
+    }
+
+    fn parse_with<T>(
+        &self,
+        column: &str,
+        parse: impl Fn(&str) -> Result<T, String>,
+    ) -> Result<T, ImportError> {
+        self.parse_optional_with(column, parse)?
+            .ok_or_else(|| self.error(format!("{} is missing", column)))
+    }
+
+    fn parse_optional_with<T>(
+        &self,
+        column: &str,
+        parse: impl Fn(&str) -> Result<T, String>,
+    ) -> Result<Option<T>, ImportError> {
+        self.field(column)
+            .map(|value| {
+                parse(value)
+                    .map_err(|message| self.error(format!("{} '{}': {}", column, value, message)))
+            })
+            .transpose()
+    }
+}
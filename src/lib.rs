@@ -1,5 +1,11 @@
 pub mod algorithm;
+pub mod binary;
+pub mod experiment;
+pub mod explain;
+pub mod export;
 pub mod generator;
+pub mod import;
 pub mod objective_value_calculator;
 pub mod problem;
+pub mod schema;
 pub mod validator;
@@ -1,42 +1,360 @@
+use std::error::Error;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use rand::rngs::StdRng;
+use rand::{thread_rng, Rng, SeedableRng};
 
-use table_problem::algorithm::v5::solve;
-use table_problem::objective_value_calculator::v1::ObjectiveValueCalculator;
-use table_problem::problem::ProblemDescription;
-use table_problem::validator::validate_solution;
+use table_problem::binary::{
+    is_binary, problem_from_msgpack, problem_to_msgpack, solution_from_msgpack,
+    solution_to_msgpack, BINARY_EXTENSION,
+};
+use table_problem::experiment::{
+    csv_report, markdown_report, run_experiment, solver_by_name, summarize, ExperimentProblem,
+    SOLVER_NAMES,
+};
+use table_problem::explain::explain;
+use table_problem::export::{export_by_name, EXPORT_FORMATS};
+use table_problem::generator::manifest::{
+    generate_files, size_preset, GenerateSettings, Manifest, MANIFEST_FILE, SIZE_PRESET_NAMES,
+};
+use table_problem::generator::profile::{GeneratorProfile, PROFILE_NAMES};
+use table_problem::import::import_csv_dir;
+use table_problem::objective_value_calculator::{solution_value_by_name, CALCULATOR_NAMES};
+use table_problem::problem::{PersonId, ProblemDescription, RelationSemantics, Solution};
+use table_problem::schema::{problem_from_json, problem_json_schema, problem_to_json};
+use table_problem::validator::{check_solution, validate_problem};
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
-    #[arg(short, long)]
-    input_dir: PathBuf,
+    #[command(subcommand)]
+    command: Command,
 }
 
-fn main() {
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Solves a problem file or every problem in a dir
+    Solve {
+        /// Problem file or dir of problems
+        #[arg(short, long)]
+        input: PathBuf,
+
+        #[arg(long, default_value = "v7", value_parser = SOLVER_NAMES)]
+        solver: String,
+
+        #[arg(long, default_value_t = 1000)]
+        time_limit_ms: i64,
+
+        /// Random if not given
+        #[arg(long)]
+        seed: Option<u64>,
+
+        /// Solution file for a problem file, dir of solutions for a dir of problems.
+        /// Solutions in a dir are MessagePack files for MessagePack problems
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Checks a problem and, if given, a solution of it
+    Validate {
+        #[arg(short, long)]
+        problem: PathBuf,
+
+        #[arg(short, long)]
+        solution: Option<PathBuf>,
+    },
+    /// Prints the objective value of a solution
+    Score {
+        #[arg(short, long)]
+        problem: PathBuf,
+
+        #[arg(short, long)]
+        solution: PathBuf,
+
+        #[arg(long, default_value = "v1", value_parser = CALCULATOR_NAMES)]
+        calculator: String,
+    },
+    /// Prints what the objective value of a solution is made of, table by table
+    Explain {
+        #[arg(short, long)]
+        problem: PathBuf,
+
+        #[arg(short, long)]
+        solution: PathBuf,
+    },
+    /// Writes the seating plan of a solution for event staff, table by table in seat order
+    Export {
+        #[arg(short, long)]
+        problem: PathBuf,
+
+        #[arg(short, long)]
+        solution: PathBuf,
+
+        /// Taken from the extension of the output file if not given
+        #[arg(long, value_parser = EXPORT_FORMATS)]
+        format: Option<String>,
+
+        /// Printed if not given
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Builds a problem from people.csv, tables.csv, visits.csv and relations.csv
+    Import {
+        /// Dir with the CSV files, visits.csv and relations.csv may be missing
+        #[arg(short, long)]
+        input_dir: PathBuf,
+
+        /// Problem file to write, MessagePack if it ends with .msgpack
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Read relations as how much the first person wants to sit next to the second one
+        #[arg(long)]
+        directed: bool,
+    },
+    /// Converts a problem file between JSON and MessagePack, told by the file extensions
+    Convert {
+        #[arg(short, long)]
+        input: PathBuf,
+
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// Prints the JSON Schema of the problem file format
+    Schema {
+        /// Written instead of printed
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Runs solvers on all problems with every seed and time limit and reports statistics per solver
+    Experiment {
+        #[arg(short, long)]
+        input_dir: PathBuf,
+
+        #[arg(long, value_delimiter = ',', default_value = "v7", value_parser = SOLVER_NAMES)]
+        solvers: Vec<String>,
+
+        #[arg(long, value_delimiter = ',', default_value = "0")]
+        seeds: Vec<u64>,
+
+        #[arg(long, value_delimiter = ',', default_value = "1000")]
+        time_limits_ms: Vec<i64>,
+
+        /// Markdown report, printed if neither report is given
+        #[arg(long)]
+        markdown: Option<PathBuf>,
+
+        #[arg(long)]
+        csv: Option<PathBuf>,
+    },
+    /// Generates problems and a manifest listing them with their parameters
+    Generate {
+        #[arg(short, long)]
+        output_dir: PathBuf,
+
+        #[arg(short, long, default_value_t = 1)]
+        no_of_problems: usize,
+
+        /// Range of the number of people
+        #[arg(long, default_value = "medium", value_parser = SIZE_PRESET_NAMES)]
+        size: String,
+
+        /// Replaces the minimum of the size preset
+        #[arg(long)]
+        min_people: Option<usize>,
+
+        /// Replaces the exclusive maximum of the size preset
+        #[arg(long)]
+        max_people: Option<usize>,
+
+        /// Same number of table days for every problem instead of deriving it from the slack
+        #[arg(long)]
+        no_of_table_days: Option<usize>,
+
+        /// Extra seats over one per person in percent of the needed seats, replaces the profile value
+        #[arg(long)]
+        capacity_slack_percent: Option<f64>,
+
+        /// Random if not given, the manifest records it either way
+        #[arg(long)]
+        seed: Option<u64>,
+
+        /// Built-in scenario to generate
+        #[arg(long, default_value = "default", value_parser = PROFILE_NAMES)]
+        profile: String,
+
+        /// TOML or JSON spec of the scenario, replaces --profile
+        #[arg(long)]
+        profile_file: Option<PathBuf>,
+
+        /// Also write the planted groups of the relation model next to each problem
+        #[arg(long)]
+        groups: bool,
+
+        /// Build each problem around a hidden optimal seating and write it with its value
+        /// next to the problem, the relation model of the profile is not used
+        #[arg(long)]
+        planted: bool,
+    },
+}
+
+fn main() -> ExitCode {
     let args = Args::parse();
 
-    let dir = fs::read_dir(args.input_dir).unwrap();
+    let result = match args.command {
+        Command::Solve {
+            input,
+            solver,
+            time_limit_ms,
+            seed,
+            output,
+        } => solve(&input, &solver, time_limit_ms, seed, output.as_deref()),
+        Command::Validate { problem, solution } => validate(&problem, solution.as_deref()),
+        Command::Score {
+            problem,
+            solution,
+            calculator,
+        } => score(&problem, &solution, &calculator),
+        Command::Explain { problem, solution } => read_problem_and_solution(&problem, &solution)
+            .map(|(problem, solution)| {
+                print!("{}", explain(&problem, &solution));
+            }),
+        Command::Export {
+            problem,
+            solution,
+            format,
+            output,
+        } => export(&problem, &solution, format.as_deref(), output.as_deref()),
+        Command::Import {
+            input_dir,
+            output,
+            directed,
+        } => import(&input_dir, &output, directed),
+        Command::Convert { input, output } => {
+            read_problem(&input).and_then(|problem| write_problem(&output, &problem))
+        }
+        Command::Schema { output } => match output {
+            Some(output) => fs::write(output, problem_json_schema()).map_err(|e| e.into()),
+            None => {
+                println!("{}", problem_json_schema());
+                Ok(())
+            }
+        },
+        Command::Experiment {
+            input_dir,
+            solvers,
+            seeds,
+            time_limits_ms,
+            markdown,
+            csv,
+        } => experiment(&input_dir, solvers, seeds, time_limits_ms, markdown, csv),
+        Command::Generate {
+            output_dir,
+            no_of_problems,
+            size,
+            min_people,
+            max_people,
+            no_of_table_days,
+            capacity_slack_percent,
+            seed,
+            profile,
+            profile_file,
+            groups,
+            planted,
+        } => {
+            let profile = match &profile_file {
+                Some(path) => GeneratorProfile::load(path),
+                None => Ok(GeneratorProfile::named(&profile).unwrap()),
+            };
+            profile.and_then(|mut profile| {
+                if let Some(capacity_slack_percent) = capacity_slack_percent {
+                    profile.capacity_slack = capacity_slack_percent / 100.0;
+                    profile.check()?;
+                }
+                let (preset_min_people, preset_max_people) = size_preset(&size).unwrap();
+                let settings = GenerateSettings {
+                    no_of_problems,
+                    min_people: min_people.unwrap_or(preset_min_people),
+                    max_people: max_people.unwrap_or(preset_max_people),
+                    no_of_table_days,
+                    seed: seed.unwrap_or_else(|| thread_rng().gen()),
+                    groups,
+                    planted,
+                    profile,
+                };
+                generate_files(&settings, &output_dir)?;
+                println!("Successfully generated {} problems", no_of_problems);
+                Ok(())
+            })
+        }
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("Error: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn solve(
+    input: &Path,
+    solver: &str,
+    time_limit_ms: i64,
+    seed: Option<u64>,
+    output: Option<&Path>,
+) -> Result<(), Box<dyn Error>> {
+    let solve = solver_by_name(solver).unwrap();
+    let mut rng = StdRng::seed_from_u64(seed.unwrap_or_else(|| thread_rng().gen()));
+    let problem_paths = if input.is_dir() {
+        if let Some(output) = output {
+            fs::create_dir_all(output)?;
+        }
+        problem_paths(input)?
+    } else {
+        vec![input.to_path_buf()]
+    };
 
     let mut results = vec![];
-    for file in dir {
-        let filename_path = file.unwrap().path();
-        let file_content = std::fs::read_to_string(&filename_path).unwrap();
-        let problem: ProblemDescription = serde_json::from_str(&file_content).unwrap();
-        let calculator = ObjectiveValueCalculator::new(&problem);
-
-        println!("--- Running algorithm for {}", filename_path.display());
-        let result = solve(&problem, chrono::Duration::seconds(1));
-        println!(
-            "Result value {}",
-            calculator.solution_value(&result.solution)
+    for problem_path in problem_paths.iter() {
+        let problem = read_problem(problem_path)?;
+        validate_problem(&problem)
+            .map_err(|error| format!("{}: {}", problem_path.display(), error))?;
+
+        println!("--- Running {} for {}", solver, problem_path.display());
+        let result = solve(
+            &problem,
+            chrono::Duration::milliseconds(time_limit_ms),
+            &mut rng,
         );
-        validate_solution(&problem, &result.solution);
+        check_solution(&problem, &result.solution)
+            .map_err(|error| format!("{}: {}", problem_path.display(), error))?;
+        println!("Result value {}", result.objective_value);
+
+        let solution_path = match output {
+            Some(output) if input.is_dir() => Some(output.join(format!(
+                "{}.solution.{}",
+                problem_path.file_stem().unwrap().to_string_lossy(),
+                if is_binary(problem_path) {
+                    BINARY_EXTENSION
+                } else {
+                    "json"
+                }
+            ))),
+            output => output.map(Path::to_path_buf),
+        };
+        if let Some(solution_path) = solution_path {
+            write_solution(&solution_path, &result.solution)?;
+        }
         results.push(result);
     }
 
+    if results.is_empty() {
+        return Err(format!("No problems found in {}", input.display()).into());
+    }
     println!("Successfully calculated");
 
     let avg_iterations = results.iter().map(|r| r.no_of_iterations).sum::<usize>() / results.len();
@@ -50,4 +368,206 @@ fn main() {
         "Avg iterations {}, avg elapsed {}ms",
         avg_iterations, avg_time
     );
+    Ok(())
+}
+
+fn validate(problem_path: &Path, solution_path: Option<&Path>) -> Result<(), Box<dyn Error>> {
+    let problem = read_problem(problem_path)?;
+    validate_problem(&problem)?;
+    println!("Problem is valid");
+    if let Some(solution_path) = solution_path {
+        check_solution(&problem, &read_solution(solution_path)?)?;
+        println!("Solution is valid");
+    }
+    Ok(())
+}
+
+fn score(
+    problem_path: &Path,
+    solution_path: &Path,
+    calculator: &str,
+) -> Result<(), Box<dyn Error>> {
+    let (problem, solution) = read_problem_and_solution(problem_path, solution_path)?;
+    println!(
+        "{}",
+        solution_value_by_name(calculator, &problem, &solution).unwrap()
+    );
+    Ok(())
+}
+
+fn export(
+    problem_path: &Path,
+    solution_path: &Path,
+    format: Option<&str>,
+    output: Option<&Path>,
+) -> Result<(), Box<dyn Error>> {
+    let format = match (format, output.and_then(|output| output.extension())) {
+        (Some(format), _) => format,
+        (None, Some(extension)) if extension == "csv" => "csv",
+        (None, Some(extension)) if extension == "md" => "markdown",
+        (None, Some(extension)) if extension == "html" || extension == "htm" => "html",
+        _ => return Err("Format is not given and can not be told from the output file".into()),
+    };
+    let (problem, solution) = read_problem_and_solution(problem_path, solution_path)?;
+    let content = export_by_name(format, &problem, &solution).unwrap();
+    match output {
+        Some(output) => fs::write(output, content)?,
+        None => print!("{}", content),
+    }
+    Ok(())
+}
+
+fn import(input_dir: &Path, output: &Path, directed: bool) -> Result<(), Box<dyn Error>> {
+    let relation_semantics = if directed {
+        RelationSemantics::Directed
+    } else {
+        RelationSemantics::Symmetric
+    };
+    let problem = import_csv_dir(input_dir, relation_semantics)?;
+    validate_problem(&problem)?;
+    write_problem(output, &problem)?;
+    println!(
+        "Imported {} people and {} tables",
+        problem.people.len(),
+        problem.tables.len()
+    );
+    Ok(())
+}
+
+fn experiment(
+    input_dir: &Path,
+    solvers: Vec<String>,
+    seeds: Vec<u64>,
+    time_limits_ms: Vec<i64>,
+    markdown: Option<PathBuf>,
+    csv: Option<PathBuf>,
+) -> Result<(), Box<dyn Error>> {
+    let problems = problem_paths(input_dir)?
+        .iter()
+        .map(|path| {
+            Ok(ExperimentProblem {
+                name: path.display().to_string(),
+                problem: read_problem(path)?,
+                groups: read_groups(path)?,
+            })
+        })
+        .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+
+    let runs = run_experiment(&problems, &solvers, &seeds, &time_limits_ms);
+    let summaries = summarize(&runs);
+    let no_of_failed_runs = runs.iter().filter(|run| run.result.is_err()).count();
+    if no_of_failed_runs > 0 {
+        eprintln!("{} of {} runs failed", no_of_failed_runs, runs.len());
+    }
+
+    if let Some(csv) = &csv {
+        fs::write(csv, csv_report(&summaries))?;
+    }
+    match &markdown {
+        Some(markdown) => fs::write(markdown, markdown_report(&summaries))?,
+        None if csv.is_none() => print!("{}", markdown_report(&summaries)),
+        None => {}
+    }
+    Ok(())
+}
+
+/// Problems listed in the manifest of a generated dir, otherwise every JSON and MessagePack
+/// file in the dir except the ones written next to problems
+fn problem_paths(input_dir: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let manifest_path = input_dir.join(MANIFEST_FILE);
+    if manifest_path.exists() {
+        let manifest: Manifest = serde_json::from_str(&fs::read_to_string(manifest_path)?)?;
+        return Ok(manifest
+            .problems
+            .iter()
+            .map(|entry| input_dir.join(&entry.file))
+            .collect());
+    }
+    let mut problem_paths = vec![];
+    for file in fs::read_dir(input_dir)? {
+        let path = file?.path();
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        if (path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+            || is_binary(&path))
+            && ![".solution", ".groups", ".optimum"]
+                .iter()
+                .any(|suffix| stem.ends_with(suffix))
+        {
+            problem_paths.push(path);
+        }
+    }
+    // Same order on every machine, so reports can be compared
+    problem_paths.sort();
+    Ok(problem_paths)
+}
+
+/// Problem files of older schema versions are upgraded while reading
+fn read_problem(path: &Path) -> Result<ProblemDescription, Box<dyn Error>> {
+    let problem = if is_binary(path) {
+        problem_from_msgpack(&read_file(path)?).map_err(|error| error.to_string())
+    } else {
+        problem_from_json(&String::from_utf8(read_file(path)?)?).map_err(|error| error.to_string())
+    };
+    Ok(problem.map_err(|error| format!("{}: {}", path.display(), error))?)
+}
+
+/// Planted groups the generator writes next to a problem, if there are any
+fn read_groups(problem_path: &Path) -> Result<Option<Vec<Vec<PersonId>>>, Box<dyn Error>> {
+    let path = problem_path.with_file_name(format!(
+        "{}.groups.json",
+        problem_path
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+    ));
+    if !path.exists() {
+        return Ok(None);
+    }
+    let groups = serde_json::from_slice(&read_file(&path)?)
+        .map_err(|error| format!("{}: {}", path.display(), error))?;
+    Ok(Some(groups))
+}
+
+fn read_solution(path: &Path) -> Result<Solution, Box<dyn Error>> {
+    let solution = if is_binary(path) {
+        solution_from_msgpack(&read_file(path)?).map_err(|error| error.to_string())
+    } else {
+        serde_json::from_slice(&read_file(path)?).map_err(|error| error.to_string())
+    };
+    Ok(solution.map_err(|error| format!("{}: {}", path.display(), error))?)
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>, String> {
+    fs::read(path).map_err(|error| format!("{}: {}", path.display(), error))
+}
+
+fn write_problem(path: &Path, problem: &ProblemDescription) -> Result<(), Box<dyn Error>> {
+    let content = if is_binary(path) {
+        problem_to_msgpack(problem)?
+    } else {
+        problem_to_json(problem)?.into_bytes()
+    };
+    Ok(fs::write(path, content).map_err(|error| format!("{}: {}", path.display(), error))?)
+}
+
+fn write_solution(path: &Path, solution: &Solution) -> Result<(), Box<dyn Error>> {
+    let content = if is_binary(path) {
+        solution_to_msgpack(solution)?
+    } else {
+        serde_json::to_vec(solution)?
+    };
+    Ok(fs::write(path, content).map_err(|error| format!("{}: {}", path.display(), error))?)
+}
+
+fn read_problem_and_solution(
+    problem_path: &Path,
+    solution_path: &Path,
+) -> Result<(ProblemDescription, Solution), Box<dyn Error>> {
+    let problem = read_problem(problem_path)?;
+    let solution = read_solution(solution_path)?;
+    // Scores of solutions that break the rules mean nothing
+    check_solution(&problem, &solution)?;
+    Ok((problem, solution))
 }
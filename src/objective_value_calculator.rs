@@ -1,3 +1,7 @@
+use ahash::{AHashMap, AHashSet};
+
+use crate::problem::{PersonId, ProblemDescription, Solution, TableLayout};
+
 pub mod v1;
 pub mod v2;
 
@@ -6,3 +10,51 @@ pub mod v3;
 pub mod v4;
 pub mod v5;
 pub mod v6;
+pub mod v7;
+
+/// Objective penalty for every additional time the same two people are neighbours
+pub const REPEATED_NEIGHBOURS_PENALTY: f64 = 1.0;
+
+pub const CALCULATOR_NAMES: [&str; 7] = ["v1", "v2", "v3", "v4", "v5", "v6", "v7"];
+
+/// Objective value of the solution computed by the calculator version with the given name
+pub fn solution_value_by_name(
+    name: &str,
+    input: &ProblemDescription,
+    solution: &Solution,
+) -> Option<f64> {
+    match name {
+        "v1" => Some(v1::ObjectiveValueCalculator::new(input).solution_value(solution)),
+        "v2" => Some(v2::ObjectiveValueCalculator::new(input).solution_value(solution)),
+        "v3" => Some(v3::ObjectiveValueCalculator::new(input).solution_value(solution)),
+        "v4" => Some(v4::ObjectiveValueCalculator::new(input).solution_value(solution)),
+        "v5" => Some(v5::ObjectiveValueCalculator::new(input).solution_value(solution)),
+        "v6" => Some(v6::ObjectiveValueCalculator::new(input).solution_value(solution)),
+        "v7" => Some(v7::ObjectiveValueCalculator::new(input).solution_value(solution)),
+        _ => None,
+    }
+}
+
+/// Counts how many times pairs of people are neighbours again after they already sat
+/// next to each other at another table
+pub fn repeated_neighbours_count<'a>(
+    tables: impl Iterator<Item = (TableLayout, &'a [PersonId])>,
+) -> usize {
+    let mut neighbours_count: AHashMap<(PersonId, PersonId), usize> = AHashMap::new();
+    for (layout, people) in tables {
+        let table_neighbours: AHashSet<(PersonId, PersonId)> = layout
+            .neighbour_seats(people.len())
+            .into_iter()
+            .map(|(seat, other_seat)| {
+                (
+                    people[seat].min(people[other_seat]),
+                    people[seat].max(people[other_seat]),
+                )
+            })
+            .collect();
+        for neighbours in table_neighbours {
+            *neighbours_count.entry(neighbours).or_default() += 1;
+        }
+    }
+    neighbours_count.values().map(|count| count - 1).sum()
+}
@@ -1,14 +1,17 @@
 use std::collections::{BTreeMap, HashMap};
 
-use chrono::Datelike;
-use itertools::Itertools;
+use chrono::{Datelike, NaiveDate};
 
-use crate::problem::{Person, PersonId, ProblemDescription, Solution, TableDay, TableDayId};
+use crate::objective_value_calculator::{repeated_neighbours_count, REPEATED_NEIGHBOURS_PENALTY};
+use crate::problem::{
+    Person, PersonId, ProblemDescription, Solution, TableDay, TableDayId, TableLayout,
+};
 
 pub struct ObjectiveValueCalculator<'a> {
     people_map: HashMap<PersonId, &'a Person>,
     table_map: HashMap<TableDayId, &'a TableDay>,
-    relations: &'a BTreeMap<PersonId, BTreeMap<PersonId, f64>>,
+    relations: BTreeMap<PersonId, BTreeMap<PersonId, f64>>,
+    has_multiple_seatings: bool,
 }
 
 impl<'a> ObjectiveValueCalculator<'a> {
@@ -20,7 +23,8 @@ impl<'a> ObjectiveValueCalculator<'a> {
         Self {
             people_map,
             table_map,
-            relations: &input.people_relations,
+            relations: input.pair_relations(),
+            has_multiple_seatings: input.people.iter().any(|p| p.required_seatings > 1),
         }
     }
 
@@ -28,8 +32,58 @@ impl<'a> ObjectiveValueCalculator<'a> {
         solution
             .solution_per_table
             .iter()
-            .map(|(table_day_id, people_ids)| self.table_value(*table_day_id, &people_ids))
-            .sum()
+            .map(|(table_day_id, people_ids)| self.table_value(*table_day_id, people_ids))
+            .sum::<f64>()
+            - solution
+                .unassigned
+                .iter()
+                .map(|person_id| self.unassigned_penalty(*person_id).unwrap_or_default())
+                .sum::<f64>()
+            - self.repeated_neighbours_penalty(
+                solution
+                    .solution_per_table
+                    .iter()
+                    .map(|(table_day_id, people_ids)| (*table_day_id, people_ids.as_slice())),
+            )
+    }
+
+    /// Penalty for people sitting next to each other on more than one table,
+    /// only possible when people are seated more than once
+    pub fn repeated_neighbours_penalty<'b>(
+        &self,
+        tables: impl Iterator<Item = (TableDayId, &'b [PersonId])>,
+    ) -> f64 {
+        if !self.has_multiple_seatings {
+            return 0.0;
+        }
+        REPEATED_NEIGHBOURS_PENALTY
+            * repeated_neighbours_count(
+                tables.map(|(table_day_id, people_ids)| {
+                    (self.table_layout(table_day_id), people_ids)
+                }),
+            ) as f64
+    }
+
+    pub fn table_layout(&self, table_day_id: TableDayId) -> TableLayout {
+        self.table_map
+            .get(&table_day_id)
+            .expect("Failed to get table details")
+            .layout
+    }
+
+    pub fn table_date(&self, table_day_id: TableDayId) -> NaiveDate {
+        self.table_map
+            .get(&table_day_id)
+            .expect("Failed to get table details")
+            .date
+            .date_naive()
+    }
+
+    pub fn unassigned_penalty(&self, person_id: PersonId) -> Option<f64> {
+        self.people_map
+            .get(&person_id)
+            .expect("Failed to get person id")
+            .unassigned_penalty
     }
 
     pub fn table_value(&self, table_day_id: TableDayId, people_ids: &[PersonId]) -> f64 {
@@ -37,43 +91,71 @@ impl<'a> ObjectiveValueCalculator<'a> {
             .table_map
             .get(&table_day_id)
             .expect("Failed to get table details");
-        let people: Vec<&Person> = people_ids
-            .iter()
-            .map(|id| *self.people_map.get(id).expect("Failed to get person id"))
-            .collect_vec();
 
         let mut result = 0.0;
-        for (seat, person) in people.iter().enumerate() {
-            if people.len() != 1 {
-                let next_seat = (seat + 1) % people.len();
-                result += self.get_relation_score(&person.id, &people[next_seat].id);
-            }
-            if let Some(most_recent_visit) = person.visits.iter().max_by_key(|v| v.at) {
-                result += (((table.date - most_recent_visit.at).num_days() - 15) as f64 / 15.0)
-                    .min(-1.0)
-                    .max(1.0)
-            } else {
-                result += 1.0
-            }
-            if !person.visits.iter().any(|v| v.table_id == table.table_id) {
-                result += 0.5;
-            }
-            if !person
-                .visits
+        for (seat, other_seat) in table.layout.neighbour_seats(people_ids.len()) {
+            result += self.get_relation_score(&people_ids[seat], &people_ids[other_seat]);
+        }
+        for person_id in people_ids {
+            result += self
+                .person_score(*person_id, table_day_id)
                 .iter()
-                .any(|v| v.at.weekday() == table.date.weekday())
-            {
-                result += 0.5;
-            }
+                .sum::<f64>();
         }
 
         result
     }
 
-    fn get_relation_score(&self, person_1_id: &PersonId, person_2_id: &PersonId) -> f64 {
+    /// Score of seating the person at the table regardless of neighbours: how long ago the
+    /// last visit was, whether the table is new to them and whether the weekday is new to them
+    pub fn person_score(&self, person_id: PersonId, table_day_id: TableDayId) -> [f64; 3] {
+        let table = self
+            .table_map
+            .get(&table_day_id)
+            .expect("Failed to get table details");
+        let person = self
+            .people_map
+            .get(&person_id)
+            .expect("Failed to get person id");
+
+        // -1 for a visit on the same day, 0 after 15 days and 1 from 30 days on
+        let recency = if let Some(most_recent_visit) = person.visits.iter().max_by_key(|v| v.at) {
+            (((table.date - most_recent_visit.at).num_days() - 15) as f64 / 15.0).clamp(-1.0, 1.0)
+        } else {
+            1.0
+        };
+        let new_table = if person.visits.iter().any(|v| v.table_id == table.table_id) {
+            0.0
+        } else {
+            0.5
+        };
+        let new_weekday = if person
+            .visits
+            .iter()
+            .any(|v| v.at.weekday() == table.date.weekday())
+        {
+            0.0
+        } else {
+            0.5
+        };
+        [recency, new_table, new_weekday]
+    }
+
+    pub fn is_available(&self, person_id: PersonId, table_day_id: TableDayId) -> bool {
+        let table = self
+            .table_map
+            .get(&table_day_id)
+            .expect("Failed to get table details");
+        self.people_map
+            .get(&person_id)
+            .expect("Failed to get person id")
+            .is_available_on(table.date)
+    }
+
+    pub fn get_relation_score(&self, person_1_id: &PersonId, person_2_id: &PersonId) -> f64 {
         self.relations
-            .get(&person_1_id.min(person_2_id))
-            .and_then(|v| v.get(&person_1_id.max(person_2_id)))
+            .get(person_1_id.min(person_2_id))
+            .and_then(|v| v.get(person_1_id.max(person_2_id)))
             .cloned()
             .unwrap_or_default()
     }
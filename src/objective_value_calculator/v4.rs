@@ -1,3 +1,4 @@
+use crate::objective_value_calculator::{repeated_neighbours_count, REPEATED_NEIGHBOURS_PENALTY};
 use crate::problem::{Person, PersonId, ProblemDescription, Solution, TableDay, TableDayId};
 use ahash::AHashMap;
 use chrono::Datelike;
@@ -20,9 +21,9 @@ impl<'a> ObjectiveValueCalculator<'a> {
             people_map,
             table_map,
             relations: input
-                .people_relations
-                .iter()
-                .map(|(id, r)| (*id, r.iter().map(|(id2, value)| (*id2, *value)).collect()))
+                .pair_relations()
+                .into_iter()
+                .map(|(id, r)| (id, r.into_iter().collect()))
                 .collect(),
         }
     }
@@ -31,8 +32,29 @@ impl<'a> ObjectiveValueCalculator<'a> {
         solution
             .solution_per_table
             .iter()
-            .map(|(table_day_id, people_ids)| self.table_value(*table_day_id, &people_ids))
-            .sum()
+            .map(|(table_day_id, people_ids)| self.table_value(*table_day_id, people_ids))
+            .sum::<f64>()
+            - solution
+                .unassigned
+                .iter()
+                .map(|person_id| {
+                    self.people_map
+                        .get(person_id)
+                        .expect("Failed to get person id")
+                        .unassigned_penalty
+                        .unwrap_or_default()
+                })
+                .sum::<f64>()
+            - REPEATED_NEIGHBOURS_PENALTY
+                * repeated_neighbours_count(solution.solution_per_table.iter().map(
+                    |(table_day_id, people_ids)| {
+                        let table = self
+                            .table_map
+                            .get(table_day_id)
+                            .expect("Failed to get table details");
+                        (table.layout, people_ids.as_slice())
+                    },
+                )) as f64
     }
 
     pub fn table_value(&self, table_day_id: TableDayId, people_ids: &[PersonId]) -> f64 {
@@ -46,22 +68,18 @@ impl<'a> ObjectiveValueCalculator<'a> {
             .collect_vec();
 
         let mut result = 0.0;
-        for (seat, person) in people.iter().enumerate() {
-            if people.len() != 1 {
-                let next_seat = (seat + 1) % people.len();
-
-                result += self
-                    .relations
-                    .get(&person.id.min(people[next_seat].id))
-                    .and_then(|v| v.get(&person.id.max(people[next_seat].id)))
-                    .cloned()
-                    .unwrap_or_default();
-            }
-
+        for (seat, other_seat) in table.layout.neighbour_seats(people.len()) {
+            result += self
+                .relations
+                .get(&people[seat].id.min(people[other_seat].id))
+                .and_then(|v| v.get(&people[seat].id.max(people[other_seat].id)))
+                .cloned()
+                .unwrap_or_default();
+        }
+        for person in people.iter() {
             if let Some(most_recent_visit) = person.visits.iter().max_by_key(|v| v.at) {
                 result += (((table.date - most_recent_visit.at).num_days() - 15) as f64 / 15.0)
-                    .min(-1.0)
-                    .max(1.0)
+                    .clamp(-1.0, 1.0)
             } else {
                 // Has not visited for more than 30 days
                 result += 1.0
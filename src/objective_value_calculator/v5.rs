@@ -1,5 +1,6 @@
+use crate::objective_value_calculator::{repeated_neighbours_count, REPEATED_NEIGHBOURS_PENALTY};
 use crate::problem::{
-    Person, PersonId, ProblemDescription, Solution, TableDay, TableDayId, TableId,
+    Person, PersonId, ProblemDescription, Solution, TableDay, TableDayId, TableId, TableLayout,
 };
 use ahash::{AHashMap, AHashSet};
 use chrono::{DateTime, Datelike, Utc, Weekday};
@@ -17,6 +18,7 @@ struct PersonInner {
     pub most_recent_visit: Option<DateTime<Utc>>,
     pub visited_tables: AHashSet<TableId>,
     pub visited_weekdays: AHashSet<Weekday>,
+    pub unassigned_penalty: Option<f64>,
 }
 
 impl From<&Person> for PersonInner {
@@ -26,6 +28,7 @@ impl From<&Person> for PersonInner {
             most_recent_visit: p.visits.iter().max_by_key(|v| v.at).map(|v| v.at),
             visited_tables: p.visits.iter().map(|v| v.table_id).collect(),
             visited_weekdays: p.visits.iter().map(|v| v.at.weekday()).collect(),
+            unassigned_penalty: p.unassigned_penalty,
         }
     }
 }
@@ -34,6 +37,7 @@ struct TableDayInner {
     pub table_id: TableId,
     pub date: DateTime<Utc>,
     pub weekday: Weekday,
+    pub layout: TableLayout,
 }
 
 impl From<&TableDay> for TableDayInner {
@@ -42,6 +46,7 @@ impl From<&TableDay> for TableDayInner {
             table_id: value.table_id,
             date: value.date,
             weekday: value.date.weekday(),
+            layout: value.layout,
         }
     }
 }
@@ -62,9 +67,9 @@ impl ObjectiveValueCalculator {
             people_map,
             table_map,
             relations: input
-                .people_relations
-                .iter()
-                .map(|(id, r)| (*id, r.iter().map(|(id2, value)| (*id2, *value)).collect()))
+                .pair_relations()
+                .into_iter()
+                .map(|(id, r)| (id, r.into_iter().collect()))
                 .collect(),
         }
     }
@@ -73,8 +78,29 @@ impl ObjectiveValueCalculator {
         solution
             .solution_per_table
             .iter()
-            .map(|(table_day_id, people_ids)| self.table_value(*table_day_id, &people_ids))
-            .sum()
+            .map(|(table_day_id, people_ids)| self.table_value(*table_day_id, people_ids))
+            .sum::<f64>()
+            - solution
+                .unassigned
+                .iter()
+                .map(|person_id| {
+                    self.people_map
+                        .get(person_id)
+                        .expect("Failed to get person id")
+                        .unassigned_penalty
+                        .unwrap_or_default()
+                })
+                .sum::<f64>()
+            - REPEATED_NEIGHBOURS_PENALTY
+                * repeated_neighbours_count(solution.solution_per_table.iter().map(
+                    |(table_day_id, people_ids)| {
+                        let table = self
+                            .table_map
+                            .get(table_day_id)
+                            .expect("Failed to get table details");
+                        (table.layout, people_ids.as_slice())
+                    },
+                )) as f64
     }
 
     pub fn table_value(&self, table_day_id: TableDayId, people_ids: &[PersonId]) -> f64 {
@@ -92,22 +118,18 @@ impl ObjectiveValueCalculator {
         }
 
         let mut result = 0.0;
-        for (seat, person) in people.iter().enumerate() {
-            if people.len() != 1 {
-                let next_seat = (seat + 1) % people.len();
-
-                result += self
-                    .relations
-                    .get(&person.id.min(people[next_seat].id))
-                    .and_then(|v| v.get(&person.id.max(people[next_seat].id)))
-                    .cloned()
-                    .unwrap_or_default();
-            }
-
+        for (seat, other_seat) in table.layout.neighbour_seats(people.len()) {
+            result += self
+                .relations
+                .get(&people[seat].id.min(people[other_seat].id))
+                .and_then(|v| v.get(&people[seat].id.max(people[other_seat].id)))
+                .cloned()
+                .unwrap_or_default();
+        }
+        for person in people.iter() {
             if let Some(most_recent_visit) = person.most_recent_visit {
                 result += (((table.date - most_recent_visit).num_days() - 15) as f64 / 15.0)
-                    .min(-1.0)
-                    .max(1.0)
+                    .clamp(-1.0, 1.0)
             } else {
                 // Has not visited for more than 30 days
                 result += 1.0
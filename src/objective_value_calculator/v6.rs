@@ -1,9 +1,10 @@
+use crate::objective_value_calculator::{repeated_neighbours_count, REPEATED_NEIGHBOURS_PENALTY};
 use crate::problem::{
-    Person, PersonId, ProblemDescription, Solution, TableDay, TableDayId, TableId,
+    Person, PersonId, ProblemDescription, Solution, TableDay, TableDayId, TableId, TableLayout,
     MAX_PEOPLE_FOR_TABLE,
 };
 use ahash::{AHashMap, AHashSet};
-use chrono::{DateTime, Datelike, Utc, Weekday};
+use chrono::{DateTime, Datelike, NaiveDate, Utc, Weekday};
 use smallvec::SmallVec;
 
 /// PersonInner and TableDayInner structs introduction
@@ -11,6 +12,7 @@ pub struct ObjectiveValueCalculator {
     people_map: AHashMap<PersonId, PersonInner>,
     table_map: AHashMap<TableDayId, TableDayInner>,
     relations: AHashMap<PersonId, AHashMap<PersonId, f64>>,
+    has_multiple_seatings: bool,
 }
 
 struct PersonInner {
@@ -18,6 +20,8 @@ struct PersonInner {
     pub most_recent_visit: Option<DateTime<Utc>>,
     pub visited_tables: AHashSet<TableId>,
     pub visited_weekdays: AHashSet<Weekday>,
+    pub unassigned_penalty: Option<f64>,
+    pub available_dates: Option<AHashSet<NaiveDate>>,
 }
 
 impl From<&Person> for PersonInner {
@@ -27,6 +31,11 @@ impl From<&Person> for PersonInner {
             most_recent_visit: p.visits.iter().max_by_key(|v| v.at).map(|v| v.at),
             visited_tables: p.visits.iter().map(|v| v.table_id).collect(),
             visited_weekdays: p.visits.iter().map(|v| v.at.weekday()).collect(),
+            unassigned_penalty: p.unassigned_penalty,
+            available_dates: p
+                .available_dates
+                .as_ref()
+                .map(|dates| dates.iter().cloned().collect()),
         }
     }
 }
@@ -35,6 +44,8 @@ struct TableDayInner {
     pub table_id: TableId,
     pub date: DateTime<Utc>,
     pub weekday: Weekday,
+    pub layout: TableLayout,
+    pub day: NaiveDate,
 }
 
 impl From<&TableDay> for TableDayInner {
@@ -43,6 +54,8 @@ impl From<&TableDay> for TableDayInner {
             table_id: value.table_id,
             date: value.date,
             weekday: value.date.weekday(),
+            layout: value.layout,
+            day: value.date.date_naive(),
         }
     }
 }
@@ -63,10 +76,11 @@ impl ObjectiveValueCalculator {
             people_map,
             table_map,
             relations: input
-                .people_relations
-                .iter()
-                .map(|(id, r)| (*id, r.iter().map(|(id2, value)| (*id2, *value)).collect()))
+                .pair_relations()
+                .into_iter()
+                .map(|(id, r)| (id, r.into_iter().collect()))
                 .collect(),
+            has_multiple_seatings: input.people.iter().any(|p| p.required_seatings > 1),
         }
     }
 
@@ -74,48 +88,285 @@ impl ObjectiveValueCalculator {
         solution
             .solution_per_table
             .iter()
-            .map(|(table_day_id, people_ids)| self.table_value(*table_day_id, &people_ids))
-            .sum()
+            .map(|(table_day_id, people_ids)| self.table_value(*table_day_id, people_ids))
+            .sum::<f64>()
+            - solution
+                .unassigned
+                .iter()
+                .map(|person_id| self.unassigned_penalty(*person_id).unwrap_or_default())
+                .sum::<f64>()
+            - self.repeated_neighbours_penalty(
+                solution
+                    .solution_per_table
+                    .iter()
+                    .map(|(table_day_id, people_ids)| (*table_day_id, people_ids.as_slice())),
+            )
     }
 
-    pub fn table_value(&self, table_day_id: TableDayId, people_ids: &[PersonId]) -> f64 {
+    /// Penalty for people sitting next to each other on more than one table,
+    /// only possible when people are seated more than once
+    pub fn repeated_neighbours_penalty<'b>(
+        &self,
+        tables: impl Iterator<Item = (TableDayId, &'b [PersonId])>,
+    ) -> f64 {
+        if !self.has_multiple_seatings {
+            return 0.0;
+        }
+        REPEATED_NEIGHBOURS_PENALTY
+            * repeated_neighbours_count(
+                tables.map(|(table_day_id, people_ids)| {
+                    (self.table_layout(table_day_id), people_ids)
+                }),
+            ) as f64
+    }
+
+    pub fn table_layout(&self, table_day_id: TableDayId) -> TableLayout {
+        self.table_map
+            .get(&table_day_id)
+            .expect("Failed to get table details")
+            .layout
+    }
+
+    pub fn table_date(&self, table_day_id: TableDayId) -> NaiveDate {
+        self.table_map
+            .get(&table_day_id)
+            .expect("Failed to get table details")
+            .day
+    }
+
+    pub fn unassigned_penalty(&self, person_id: PersonId) -> Option<f64> {
+        self.people_map
+            .get(&person_id)
+            .expect("Failed to get person id")
+            .unassigned_penalty
+    }
+
+    pub fn is_available(&self, person_id: PersonId, table_day_id: TableDayId) -> bool {
         let table = self
             .table_map
             .get(&table_day_id)
             .expect("Failed to get table details");
-        let people: SmallVec<[&PersonInner; MAX_PEOPLE_FOR_TABLE]> = people_ids
-            .iter()
-            .map(|id| self.people_map.get(id).expect("Failed to get person id"))
-            .collect();
+        self.people_map
+            .get(&person_id)
+            .expect("Failed to get person id")
+            .available_dates
+            .as_ref()
+            .is_none_or(|dates| dates.contains(&table.day))
+    }
+
+    pub fn table_value(&self, table_day_id: TableDayId, people_ids: &[PersonId]) -> f64 {
+        let table = self.table(table_day_id);
+        let people: SmallVec<[&PersonInner; MAX_PEOPLE_FOR_TABLE]> =
+            people_ids.iter().map(|id| self.person(*id)).collect();
 
         let mut result = 0.0;
-        for (seat, person) in people.iter().enumerate() {
-            if people.len() != 1 {
-                let next_seat = (seat + 1) % people.len();
-                result += self
-                    .relations
-                    .get(&person.id.min(people[next_seat].id))
-                    .and_then(|v| v.get(&person.id.max(people[next_seat].id)))
-                    .cloned()
-                    .unwrap_or_default();
+        for (seat, other_seat) in table.layout.neighbour_seats(people.len()) {
+            result += self.relation_score(people[seat].id, people[other_seat].id);
+        }
+        for person in people.iter() {
+            result += self.person_score(person, table);
+        }
+        result
+    }
+
+    /// Change of the table value after inserting the person before the given seat index
+    pub fn insertion_delta(
+        &self,
+        table_day_id: TableDayId,
+        people_ids: &[PersonId],
+        seat: usize,
+        person_id: PersonId,
+    ) -> f64 {
+        let table = self.table(table_day_id);
+        let person_value = self.person_score(self.person(person_id), table);
+        match (table.layout, people_ids.len()) {
+            (TableLayout::Ring, 0) => person_value,
+            // Two people on a round table are neighbours on both sides
+            (TableLayout::Ring, 1) => {
+                person_value + 2.0 * self.relation_score(person_id, people_ids[0])
             }
-            if let Some(most_recent_visit) = person.most_recent_visit {
-                result += (((table.date - most_recent_visit).num_days() - 15) as f64 / 15.0)
-                    .min(-1.0)
-                    .max(1.0)
-            } else {
-                // Has not visited for more than 30 days
-                result += 1.0
+            (TableLayout::Ring, no_of_people) => {
+                let previous = people_ids[(seat + no_of_people - 1) % no_of_people];
+                let next = people_ids[seat % no_of_people];
+                person_value
+                    + self.relation_score(previous, person_id)
+                    + self.relation_score(person_id, next)
+                    - self.relation_score(previous, next)
+            }
+            _ => {
+                let mut updated_people_ids: SmallVec<[PersonId; MAX_PEOPLE_FOR_TABLE]> =
+                    SmallVec::from_slice(people_ids);
+                updated_people_ids.insert(seat, person_id);
+                person_value + self.rearrangement_delta(table, people_ids, &updated_people_ids)
+            }
+        }
+    }
+
+    /// Change of the table value after removing the person sitting on the given seat index
+    pub fn removal_delta(
+        &self,
+        table_day_id: TableDayId,
+        people_ids: &[PersonId],
+        seat: usize,
+    ) -> f64 {
+        let table = self.table(table_day_id);
+        let person_id = people_ids[seat];
+        let person_value = self.person_score(self.person(person_id), table);
+        match (table.layout, people_ids.len()) {
+            (TableLayout::Ring, 1) => -person_value,
+            (TableLayout::Ring, 2) => {
+                -person_value - 2.0 * self.relation_score(people_ids[0], people_ids[1])
             }
-            if !person.visited_tables.contains(&table.table_id) {
-                // Add value if never visited this table in the past
-                result += 0.5;
+            (TableLayout::Ring, no_of_people) => {
+                let previous = people_ids[(seat + no_of_people - 1) % no_of_people];
+                let next = people_ids[(seat + 1) % no_of_people];
+                -person_value
+                    - self.relation_score(previous, person_id)
+                    - self.relation_score(person_id, next)
+                    + self.relation_score(previous, next)
             }
-            if !person.visited_weekdays.contains(&table.weekday) {
-                // Add value if never visited this table in this weekday
-                result += 0.5;
+            _ => {
+                let mut updated_people_ids: SmallVec<[PersonId; MAX_PEOPLE_FOR_TABLE]> =
+                    SmallVec::from_slice(people_ids);
+                updated_people_ids.remove(seat);
+                -person_value + self.rearrangement_delta(table, people_ids, &updated_people_ids)
             }
         }
+    }
+
+    /// Change of the objective value after two people swap their seats,
+    /// both seats can be at the same table
+    pub fn swap_delta(
+        &self,
+        (table_day_id_1, people_ids_1, seat_1): (TableDayId, &[PersonId], usize),
+        (table_day_id_2, people_ids_2, seat_2): (TableDayId, &[PersonId], usize),
+    ) -> f64 {
+        if table_day_id_1 == table_day_id_2 {
+            let mut updated_people_ids: SmallVec<[PersonId; MAX_PEOPLE_FOR_TABLE]> =
+                SmallVec::from_slice(people_ids_1);
+            updated_people_ids.swap(seat_1, seat_2);
+            self.rearrangement_delta(
+                self.table(table_day_id_1),
+                people_ids_1,
+                &updated_people_ids,
+            )
+        } else {
+            self.replacement_delta(table_day_id_1, people_ids_1, seat_1, people_ids_2[seat_2])
+                + self.replacement_delta(table_day_id_2, people_ids_2, seat_2, people_ids_1[seat_1])
+        }
+    }
+
+    /// Change of the objective value after moving a person to another seat, `to_seat` is the
+    /// insertion index in the target table once the person has left their seat
+    pub fn move_delta(
+        &self,
+        (from_table_day_id, from_people_ids, from_seat): (TableDayId, &[PersonId], usize),
+        (to_table_day_id, to_people_ids, to_seat): (TableDayId, &[PersonId], usize),
+    ) -> f64 {
+        if from_table_day_id == to_table_day_id {
+            let mut updated_people_ids: SmallVec<[PersonId; MAX_PEOPLE_FOR_TABLE]> =
+                SmallVec::from_slice(from_people_ids);
+            let person_id = updated_people_ids.remove(from_seat);
+            updated_people_ids.insert(to_seat, person_id);
+            self.rearrangement_delta(
+                self.table(from_table_day_id),
+                from_people_ids,
+                &updated_people_ids,
+            )
+        } else {
+            self.removal_delta(from_table_day_id, from_people_ids, from_seat)
+                + self.insertion_delta(
+                    to_table_day_id,
+                    to_people_ids,
+                    to_seat,
+                    from_people_ids[from_seat],
+                )
+        }
+    }
+
+    /// Change of the table value after the person on the seat is replaced with another one
+    fn replacement_delta(
+        &self,
+        table_day_id: TableDayId,
+        people_ids: &[PersonId],
+        seat: usize,
+        new_person_id: PersonId,
+    ) -> f64 {
+        let table = self.table(table_day_id);
+        let old_person_id = people_ids[seat];
+        let mut result = self.person_score(self.person(new_person_id), table)
+            - self.person_score(self.person(old_person_id), table);
+        for (seat_1, seat_2) in table.layout.neighbour_seats(people_ids.len()) {
+            let other_seat = if seat_1 == seat {
+                seat_2
+            } else if seat_2 == seat {
+                seat_1
+            } else {
+                continue;
+            };
+            result += self.relation_score(new_person_id, people_ids[other_seat])
+                - self.relation_score(old_person_id, people_ids[other_seat]);
+        }
+        result
+    }
+
+    /// Change of relation scores only, for the same people seated in a different order
+    fn rearrangement_delta(
+        &self,
+        table: &TableDayInner,
+        people_ids: &[PersonId],
+        updated_people_ids: &[PersonId],
+    ) -> f64 {
+        self.relations_value(table.layout, updated_people_ids)
+            - self.relations_value(table.layout, people_ids)
+    }
+
+    fn relations_value(&self, layout: TableLayout, people_ids: &[PersonId]) -> f64 {
+        layout
+            .neighbour_seats(people_ids.len())
+            .into_iter()
+            .map(|(seat, other_seat)| self.relation_score(people_ids[seat], people_ids[other_seat]))
+            .sum()
+    }
+
+    fn relation_score(&self, person_1_id: PersonId, person_2_id: PersonId) -> f64 {
+        self.relations
+            .get(&person_1_id.min(person_2_id))
+            .and_then(|v| v.get(&person_1_id.max(person_2_id)))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Value of the person sitting at the table regardless of neighbours
+    fn person_score(&self, person: &PersonInner, table: &TableDayInner) -> f64 {
+        let mut result = 0.0;
+        if let Some(most_recent_visit) = person.most_recent_visit {
+            result +=
+                (((table.date - most_recent_visit).num_days() - 15) as f64 / 15.0).clamp(-1.0, 1.0)
+        } else {
+            // Has not visited for more than 30 days
+            result += 1.0
+        }
+        if !person.visited_tables.contains(&table.table_id) {
+            // Add value if never visited this table in the past
+            result += 0.5;
+        }
+        if !person.visited_weekdays.contains(&table.weekday) {
+            // Add value if never visited this table in this weekday
+            result += 0.5;
+        }
         result
     }
+
+    fn person(&self, person_id: PersonId) -> &PersonInner {
+        self.people_map
+            .get(&person_id)
+            .expect("Failed to get person id")
+    }
+
+    fn table(&self, table_day_id: TableDayId) -> &TableDayInner {
+        self.table_map
+            .get(&table_day_id)
+            .expect("Failed to get table details")
+    }
 }
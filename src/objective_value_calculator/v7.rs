@@ -0,0 +1,558 @@
+use crate::objective_value_calculator::{repeated_neighbours_count, REPEATED_NEIGHBOURS_PENALTY};
+use crate::problem::{
+    PersonId, ProblemDescription, Solution, TableDayId, TableLayout, MAX_PEOPLE_FOR_TABLE,
+};
+use std::borrow::Cow;
+
+use ahash::{AHashMap, AHashSet};
+use chrono::{Datelike, NaiveDate};
+use smallvec::SmallVec;
+
+/// Above this number of people relations are kept in a hash map instead of a full matrix
+const MAX_PEOPLE_FOR_DENSE_RELATIONS: usize = 2048;
+
+/// Dense indexes with precomputed person x table day scores
+pub struct ObjectiveValueCalculator {
+    person_indexes: AHashMap<PersonId, usize>,
+    table_day_indexes: AHashMap<TableDayId, usize>,
+    person_ids: Vec<PersonId>,
+    table_day_ids: Vec<TableDayId>,
+    /// Table days that are not scored by the vectorised ring insertion
+    non_ring_table_days: Vec<usize>,
+    people: Vec<PersonInner>,
+    table_days: Vec<TableDayInner>,
+    /// Value of a person sitting at a table day regardless of neighbours, one row per person
+    person_scores: Vec<f64>,
+    /// Whether a person can sit at a table day, same layout as person_scores
+    availability: Vec<bool>,
+    relations: RelationMatrix,
+    has_multiple_seatings: bool,
+}
+
+struct PersonInner {
+    pub unassigned_penalty: Option<f64>,
+}
+
+struct TableDayInner {
+    pub layout: TableLayout,
+    pub day: NaiveDate,
+}
+
+enum RelationMatrix {
+    /// Full matrix with an extra zero column, so the index one past the last person
+    /// can stand for an empty seat
+    Dense {
+        no_of_people: usize,
+        scores: Vec<f64>,
+    },
+    /// Relations of every person sorted by the other person index
+    Sparse {
+        no_of_people: usize,
+        scores: Vec<Vec<(usize, f64)>>,
+    },
+}
+
+impl RelationMatrix {
+    fn score(&self, person_1_index: usize, person_2_index: usize) -> f64 {
+        match self {
+            RelationMatrix::Dense {
+                no_of_people,
+                scores,
+            } => scores[person_1_index * (no_of_people + 1) + person_2_index],
+            RelationMatrix::Sparse { scores, .. } => scores
+                .get(person_1_index)
+                .and_then(|person_scores| {
+                    person_scores
+                        .binary_search_by_key(&person_2_index, |(index, _)| *index)
+                        .ok()
+                        .map(|position| person_scores[position].1)
+                })
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Scores of the person with everybody, with a zero score for the empty seat index at the end
+    fn row(&self, person_index: usize) -> Cow<'_, [f64]> {
+        match self {
+            RelationMatrix::Dense {
+                no_of_people,
+                scores,
+            } => Cow::Borrowed(
+                &scores[person_index * (no_of_people + 1)..(person_index + 1) * (no_of_people + 1)],
+            ),
+            RelationMatrix::Sparse {
+                no_of_people,
+                scores,
+            } => {
+                let mut row = vec![0.0; no_of_people + 1];
+                for (other_person_index, score) in scores[person_index].iter() {
+                    row[*other_person_index] = *score;
+                }
+                Cow::Owned(row)
+            }
+        }
+    }
+}
+
+impl ObjectiveValueCalculator {
+    pub fn new(input: &ProblemDescription) -> Self {
+        let person_indexes: AHashMap<PersonId, usize> = input
+            .people
+            .iter()
+            .enumerate()
+            .map(|(index, p)| (p.id, index))
+            .collect();
+        let table_day_indexes: AHashMap<TableDayId, usize> = input
+            .tables
+            .iter()
+            .enumerate()
+            .map(|(index, t)| (t.id, index))
+            .collect();
+
+        let mut person_scores = Vec::with_capacity(input.people.len() * input.tables.len());
+        let mut availability = Vec::with_capacity(input.people.len() * input.tables.len());
+        for person in input.people.iter() {
+            let most_recent_visit = person.visits.iter().map(|v| v.at).max();
+            let visited_tables: AHashSet<_> = person.visits.iter().map(|v| v.table_id).collect();
+            let visited_weekdays: AHashSet<_> =
+                person.visits.iter().map(|v| v.at.weekday()).collect();
+            for table in input.tables.iter() {
+                let mut score = 0.0;
+                if let Some(most_recent_visit) = most_recent_visit {
+                    score += (((table.date - most_recent_visit).num_days() - 15) as f64 / 15.0)
+                        .clamp(-1.0, 1.0)
+                } else {
+                    // Has not visited for more than 30 days
+                    score += 1.0
+                }
+                if !visited_tables.contains(&table.table_id) {
+                    // Add value if never visited this table in the past
+                    score += 0.5;
+                }
+                if !visited_weekdays.contains(&table.date.weekday()) {
+                    // Add value if never visited this table in this weekday
+                    score += 0.5;
+                }
+                person_scores.push(score);
+                availability.push(person.is_available_on(table.date));
+            }
+        }
+
+        let pair_relations = input
+            .pair_relations()
+            .into_iter()
+            .flat_map(|(id1, r)| r.into_iter().map(move |(id2, value)| (id1, id2, value)));
+        let no_of_people = input.people.len();
+        let relations = if no_of_people <= MAX_PEOPLE_FOR_DENSE_RELATIONS {
+            let mut scores = vec![0.0; no_of_people * (no_of_people + 1)];
+            for (id1, id2, value) in pair_relations {
+                if let (Some(index1), Some(index2)) =
+                    (person_indexes.get(&id1), person_indexes.get(&id2))
+                {
+                    scores[index1 * (no_of_people + 1) + index2] = value;
+                    scores[index2 * (no_of_people + 1) + index1] = value;
+                }
+            }
+            RelationMatrix::Dense {
+                no_of_people,
+                scores,
+            }
+        } else {
+            let mut scores = vec![vec![]; no_of_people];
+            for (id1, id2, value) in pair_relations {
+                if let (Some(index1), Some(index2)) =
+                    (person_indexes.get(&id1), person_indexes.get(&id2))
+                {
+                    scores[*index1].push((*index2, value));
+                    scores[*index2].push((*index1, value));
+                }
+            }
+            for person_scores in scores.iter_mut() {
+                person_scores.sort_by_key(|(index, _)| *index);
+            }
+            RelationMatrix::Sparse {
+                no_of_people,
+                scores,
+            }
+        };
+
+        Self {
+            person_indexes,
+            table_day_indexes,
+            person_ids: input.people.iter().map(|p| p.id).collect(),
+            table_day_ids: input.tables.iter().map(|t| t.id).collect(),
+            non_ring_table_days: input
+                .tables
+                .iter()
+                .enumerate()
+                .filter(|(_, t)| t.layout != TableLayout::Ring)
+                .map(|(index, _)| index)
+                .collect(),
+            people: input
+                .people
+                .iter()
+                .map(|p| PersonInner {
+                    unassigned_penalty: p.unassigned_penalty,
+                })
+                .collect(),
+            table_days: input
+                .tables
+                .iter()
+                .map(|t| TableDayInner {
+                    layout: t.layout,
+                    day: t.date.date_naive(),
+                })
+                .collect(),
+            person_scores,
+            availability,
+            relations,
+            has_multiple_seatings: input.people.iter().any(|p| p.required_seatings > 1),
+        }
+    }
+
+    pub fn solution_value(&self, solution: &Solution) -> f64 {
+        solution
+            .solution_per_table
+            .iter()
+            .map(|(table_day_id, people_ids)| self.table_value(*table_day_id, people_ids))
+            .sum::<f64>()
+            - solution
+                .unassigned
+                .iter()
+                .map(|person_id| self.unassigned_penalty(*person_id).unwrap_or_default())
+                .sum::<f64>()
+            - self.repeated_neighbours_penalty(
+                solution
+                    .solution_per_table
+                    .iter()
+                    .map(|(table_day_id, people_ids)| (*table_day_id, people_ids.as_slice())),
+            )
+    }
+
+    /// Penalty for people sitting next to each other on more than one table,
+    /// only possible when people are seated more than once
+    pub fn repeated_neighbours_penalty<'b>(
+        &self,
+        tables: impl Iterator<Item = (TableDayId, &'b [PersonId])>,
+    ) -> f64 {
+        if !self.has_multiple_seatings {
+            return 0.0;
+        }
+        REPEATED_NEIGHBOURS_PENALTY
+            * repeated_neighbours_count(
+                tables.map(|(table_day_id, people_ids)| {
+                    (self.table_layout(table_day_id), people_ids)
+                }),
+            ) as f64
+    }
+
+    pub fn table_layout(&self, table_day_id: TableDayId) -> TableLayout {
+        self.table_days[self.table_day_index(table_day_id)].layout
+    }
+
+    pub fn table_date(&self, table_day_id: TableDayId) -> NaiveDate {
+        self.table_days[self.table_day_index(table_day_id)].day
+    }
+
+    pub fn unassigned_penalty(&self, person_id: PersonId) -> Option<f64> {
+        self.people[self.person_index(person_id)].unassigned_penalty
+    }
+
+    pub fn is_available(&self, person_id: PersonId, table_day_id: TableDayId) -> bool {
+        self.is_available_by_index(
+            self.person_index(person_id),
+            self.table_day_index(table_day_id),
+        )
+    }
+
+    pub fn table_value(&self, table_day_id: TableDayId, people_ids: &[PersonId]) -> f64 {
+        let people_indexes: SmallVec<[usize; MAX_PEOPLE_FOR_TABLE]> =
+            people_ids.iter().map(|id| self.person_index(*id)).collect();
+        self.table_value_by_index(self.table_day_index(table_day_id), &people_indexes)
+    }
+
+    pub fn person_index(&self, person_id: PersonId) -> usize {
+        *self
+            .person_indexes
+            .get(&person_id)
+            .expect("Failed to get person id")
+    }
+
+    pub fn table_day_index(&self, table_day_id: TableDayId) -> usize {
+        *self
+            .table_day_indexes
+            .get(&table_day_id)
+            .expect("Failed to get table details")
+    }
+
+    pub fn is_available_by_index(&self, person_index: usize, table_day_index: usize) -> bool {
+        self.availability[person_index * self.table_days.len() + table_day_index]
+    }
+
+    pub fn table_value_by_index(&self, table_day_index: usize, people_indexes: &[usize]) -> f64 {
+        let mut result = 0.0;
+        for (seat, other_seat) in self.table_days[table_day_index]
+            .layout
+            .neighbour_seats(people_indexes.len())
+        {
+            result += self
+                .relations
+                .score(people_indexes[seat], people_indexes[other_seat]);
+        }
+        for person_index in people_indexes {
+            result += self.person_score(*person_index, table_day_index);
+        }
+        result
+    }
+
+    /// Value of the person sitting at the table regardless of neighbours
+    pub fn person_score(&self, person_index: usize, table_day_index: usize) -> f64 {
+        self.person_scores[person_index * self.table_days.len() + table_day_index]
+    }
+
+    pub fn relation_score(&self, person_1_index: usize, person_2_index: usize) -> f64 {
+        self.relations.score(person_1_index, person_2_index)
+    }
+
+    pub fn person_id(&self, person_index: usize) -> PersonId {
+        self.person_ids[person_index]
+    }
+
+    pub fn table_day_id(&self, table_day_index: usize) -> TableDayId {
+        self.table_day_ids[table_day_index]
+    }
+
+    pub fn no_of_people(&self) -> usize {
+        self.people.len()
+    }
+
+    pub fn no_of_table_days(&self) -> usize {
+        self.table_days.len()
+    }
+
+    pub fn has_multiple_seatings(&self) -> bool {
+        self.has_multiple_seatings
+    }
+
+    pub fn table_date_by_index(&self, table_day_index: usize) -> NaiveDate {
+        self.table_days[table_day_index].day
+    }
+
+    pub fn unassigned_penalty_by_index(&self, person_index: usize) -> Option<f64> {
+        self.people[person_index].unassigned_penalty
+    }
+
+    /// Same as repeated_neighbours_penalty, but for the whole seating
+    pub fn seating_repeated_neighbours_penalty(&self, seating: &TableDaysSeating) -> f64 {
+        if !self.has_multiple_seatings {
+            return 0.0;
+        }
+        let tables = (0..self.table_days.len())
+            .map(|table_day_index| (table_day_index, seating.people(table_day_index)))
+            .collect::<Vec<_>>();
+        REPEATED_NEIGHBOURS_PENALTY
+            * repeated_neighbours_count(tables.iter().map(|(table_day_index, people_indexes)| {
+                (
+                    self.table_days[*table_day_index].layout,
+                    people_indexes.as_slice(),
+                )
+            })) as f64
+    }
+
+    /// Change of the objective after taking the person on the seat away from the table
+    pub fn removal_value(
+        &self,
+        seating: &TableDaysSeating,
+        table_day_index: usize,
+        seat: usize,
+    ) -> f64 {
+        let mut people_indexes = seating.people(table_day_index);
+        let before = self.table_value_by_index(table_day_index, &people_indexes);
+        people_indexes.remove(seat);
+        self.table_value_by_index(table_day_index, &people_indexes) - before
+    }
+
+    /// Scores inserting the person into every table day at once. For every table day `values`
+    /// gets the best change of the objective (negative infinity if the person can not sit there)
+    /// and `positions` the seat that achieves it.
+    ///
+    /// Round tables are scored column by column over the seating arrays, so the inner loops run
+    /// over contiguous memory and can be vectorised, other layouts fall back to rescoring the table.
+    pub fn insertion_values(
+        &self,
+        seating: &TableDaysSeating,
+        person_index: usize,
+        values: &mut Vec<f64>,
+        positions: &mut Vec<usize>,
+    ) {
+        let no_of_table_days = self.table_days.len();
+        let relations = self.relations.row(person_index);
+        let relations = relations.as_ref();
+
+        values.clear();
+        values.extend(
+            seating.seats[0]
+                .iter()
+                .zip(seating.last_seats.iter())
+                .zip(seating.closing_scores.iter())
+                .map(|((first, last), closing_score)| {
+                    relations[*first as usize] + relations[*last as usize] - closing_score
+                }),
+        );
+        positions.clear();
+        positions.resize(no_of_table_days, 0);
+
+        for seat in 1..MAX_PEOPLE_FOR_TABLE {
+            let previous_seats = &seating.seats[seat - 1];
+            let seats = &seating.seats[seat];
+            let next_seat_scores = &seating.next_seat_scores[seat - 1];
+            for table_day_index in 0..no_of_table_days {
+                // Empty seats hold the index one past the last person, scoring zero
+                let value = if seat < seating.no_of_people[table_day_index] {
+                    relations[previous_seats[table_day_index] as usize]
+                        + relations[seats[table_day_index] as usize]
+                        - next_seat_scores[table_day_index]
+                } else {
+                    f64::NEG_INFINITY
+                };
+                if value > values[table_day_index] {
+                    values[table_day_index] = value;
+                    positions[table_day_index] = seat;
+                }
+            }
+        }
+
+        let person_scores = &self.person_scores
+            [person_index * no_of_table_days..(person_index + 1) * no_of_table_days];
+        let availability = &self.availability
+            [person_index * no_of_table_days..(person_index + 1) * no_of_table_days];
+        for (((value, person_score), available), no_of_people) in values
+            .iter_mut()
+            .zip(person_scores.iter())
+            .zip(availability.iter())
+            .zip(seating.no_of_people.iter())
+        {
+            *value = if *available && *no_of_people < MAX_PEOPLE_FOR_TABLE {
+                *value + person_score
+            } else {
+                f64::NEG_INFINITY
+            };
+        }
+
+        for table_day_index in self.non_ring_table_days.iter().copied() {
+            if values[table_day_index] == f64::NEG_INFINITY {
+                continue;
+            }
+            let people_indexes = seating.people(table_day_index);
+            let current_value = self.table_value_by_index(table_day_index, &people_indexes);
+            values[table_day_index] = f64::NEG_INFINITY;
+            for insertion_index in 0..self.table_days[table_day_index]
+                .layout
+                .insertion_positions(people_indexes.len())
+            {
+                let mut updated_people = people_indexes.clone();
+                updated_people.insert(insertion_index, person_index);
+                let value =
+                    self.table_value_by_index(table_day_index, &updated_people) - current_value;
+                if value > values[table_day_index] {
+                    values[table_day_index] = value;
+                    positions[table_day_index] = insertion_index;
+                }
+            }
+        }
+    }
+}
+
+/// People at every table day kept as structure of arrays, seat by seat, for batch scoring
+/// with calculator v7
+#[derive(Clone)]
+pub struct TableDaysSeating {
+    /// Person index on the seat for every table day, empty seats hold the number of people
+    seats: [Vec<u32>; MAX_PEOPLE_FOR_TABLE],
+    /// Relation score between the seat and the next one, zero if there is no next person
+    next_seat_scores: [Vec<f64>; MAX_PEOPLE_FOR_TABLE],
+    last_seats: Vec<u32>,
+    /// Relation score between the last and the first person closing the ring
+    closing_scores: Vec<f64>,
+    no_of_people: Vec<usize>,
+}
+
+impl TableDaysSeating {
+    pub fn new(calculator: &ObjectiveValueCalculator) -> Self {
+        let no_of_table_days = calculator.no_of_table_days();
+        let empty_seat = calculator.no_of_people() as u32;
+        Self {
+            seats: std::array::from_fn(|_| vec![empty_seat; no_of_table_days]),
+            next_seat_scores: std::array::from_fn(|_| vec![0.0; no_of_table_days]),
+            last_seats: vec![empty_seat; no_of_table_days],
+            closing_scores: vec![0.0; no_of_table_days],
+            no_of_people: vec![0; no_of_table_days],
+        }
+    }
+
+    pub fn no_of_people(&self, table_day_index: usize) -> usize {
+        self.no_of_people[table_day_index]
+    }
+
+    pub fn people(&self, table_day_index: usize) -> SmallVec<[usize; MAX_PEOPLE_FOR_TABLE]> {
+        (0..self.no_of_people[table_day_index])
+            .map(|seat| self.seats[seat][table_day_index] as usize)
+            .collect()
+    }
+
+    pub fn insert(
+        &mut self,
+        calculator: &ObjectiveValueCalculator,
+        table_day_index: usize,
+        seat: usize,
+        person_index: usize,
+    ) {
+        let mut people_indexes = self.people(table_day_index);
+        people_indexes.insert(seat, person_index);
+        self.set_people(calculator, table_day_index, &people_indexes);
+    }
+
+    /// Returns the person index that was sitting on the seat
+    pub fn remove(
+        &mut self,
+        calculator: &ObjectiveValueCalculator,
+        table_day_index: usize,
+        seat: usize,
+    ) -> usize {
+        let mut people_indexes = self.people(table_day_index);
+        let person_index = people_indexes.remove(seat);
+        self.set_people(calculator, table_day_index, &people_indexes);
+        person_index
+    }
+
+    fn set_people(
+        &mut self,
+        calculator: &ObjectiveValueCalculator,
+        table_day_index: usize,
+        people_indexes: &[usize],
+    ) {
+        let empty_seat = calculator.no_of_people() as u32;
+        for seat in 0..MAX_PEOPLE_FOR_TABLE {
+            self.seats[seat][table_day_index] = people_indexes
+                .get(seat)
+                .map_or(empty_seat, |person_index| *person_index as u32);
+            self.next_seat_scores[seat][table_day_index] =
+                match (people_indexes.get(seat), people_indexes.get(seat + 1)) {
+                    (Some(person_index), Some(next_person_index)) => {
+                        calculator.relation_score(*person_index, *next_person_index)
+                    }
+                    _ => 0.0,
+                };
+        }
+        self.last_seats[table_day_index] = people_indexes
+            .last()
+            .map_or(empty_seat, |person_index| *person_index as u32);
+        // A single person does not sit next to themselves
+        self.closing_scores[table_day_index] = if people_indexes.len() > 1 {
+            calculator.relation_score(people_indexes[people_indexes.len() - 1], people_indexes[0])
+        } else {
+            0.0
+        };
+        self.no_of_people[table_day_index] = people_indexes.len();
+    }
+}
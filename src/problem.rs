@@ -1,7 +1,11 @@
 use chrono::serde::ts_seconds;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
+use itertools::Itertools;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeMap, HashMap};
+use smallvec::SmallVec;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::iter;
 
 pub const MAX_PEOPLE_FOR_TABLE: usize = 6;
 
@@ -9,7 +13,7 @@ pub type PersonId = usize;
 pub type TableId = usize;
 pub type TableDayId = usize;
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 pub struct ProblemDescription {
     /// List of people to allocate to seats
     pub people: Vec<Person>,
@@ -17,42 +21,197 @@ pub struct ProblemDescription {
     pub tables: Vec<TableDay>,
     /// How much one person wants to sit next to another, the higher value the better
     pub people_relations: BTreeMap<PersonId, BTreeMap<PersonId, f64>>,
+    /// How scores in people_relations should be read
+    #[serde(default)]
+    pub relation_semantics: RelationSemantics,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RelationSemantics {
+    /// One score per pair of people, given in either order
+    #[default]
+    Symmetric,
+    /// Score of how much the first person wants to sit next to the second one,
+    /// neighbours get the scores of both directions summed
+    Directed,
+}
+
+impl ProblemDescription {
+    /// Score of sitting next to each other for every pair of people, keyed by the lower id first
+    pub fn pair_relations(&self) -> BTreeMap<PersonId, BTreeMap<PersonId, f64>> {
+        let mut result: BTreeMap<PersonId, BTreeMap<PersonId, f64>> = BTreeMap::new();
+        for (person_1_id, relations) in self.people_relations.iter() {
+            for (person_2_id, score) in relations.iter() {
+                if person_1_id == person_2_id {
+                    continue;
+                }
+                let pair_score = result
+                    .entry(*person_1_id.min(person_2_id))
+                    .or_default()
+                    .entry(*person_1_id.max(person_2_id));
+                match self.relation_semantics {
+                    RelationSemantics::Directed => *pair_score.or_default() += score,
+                    // Entry with the lower id first takes precedence if the pair is given twice
+                    RelationSemantics::Symmetric if person_1_id < person_2_id => {
+                        pair_score.and_modify(|v| *v = *score).or_insert(*score);
+                    }
+                    RelationSemantics::Symmetric => {
+                        pair_score.or_insert(*score);
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    /// People ids repeated for every seating they require, the ones with the fewest spare
+    /// dates to choose from come first
+    pub fn seatings_by_availability(&self) -> impl Iterator<Item = PersonId> + '_ {
+        self.people
+            .iter()
+            .sorted_by_key(|p| {
+                p.available_dates.as_ref().map_or(usize::MAX, |dates| {
+                    dates.len().saturating_sub(p.required_seatings)
+                })
+            })
+            .flat_map(|p| iter::repeat_n(p.id, p.required_seatings))
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 pub struct Person {
     /// Person unique ID
     pub id: PersonId,
     /// Past visits
     pub visits: Vec<PersonVisit>,
+    /// Dates the person registered for, if not set the person can be seated on any date
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub available_dates: Option<BTreeSet<NaiveDate>>,
+    /// Objective penalty for leaving the person without a seat, if not set the person must be seated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unassigned_penalty: Option<f64>,
+    /// Number of dates the person has to be seated on, at most one seating per date
+    #[serde(default = "default_required_seatings")]
+    pub required_seatings: usize,
+    /// Shown in exports and explanations instead of the id, not used by solvers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Contact address for the CSV export, not used by solvers and not checked to be valid
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    /// Free-form labels, not used by solvers. CSV files list them separated by `;`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Anything else the source system knows about the person, carried along unchanged
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
+}
+
+fn default_required_seatings() -> usize {
+    1
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+impl Person {
+    pub fn is_available_on(&self, date: DateTime<Utc>) -> bool {
+        self.available_dates
+            .as_ref()
+            .is_none_or(|dates| dates.contains(&date.date_naive()))
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 pub struct PersonVisit {
     pub table_id: TableId,
+    /// Unix timestamp in seconds
     #[serde(with = "ts_seconds")]
+    #[schemars(with = "i64")]
     pub at: DateTime<Utc>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 pub struct TableDay {
     /// Unique table id for a given day, this is not a table id
     pub id: TableDayId,
     /// Unique table id
     pub table_id: TableId,
-    /// Table visit date
+    /// Table visit date as a Unix timestamp in seconds
     #[serde(with = "ts_seconds")]
+    #[schemars(with = "i64")]
     pub date: DateTime<Utc>,
+    /// Shape of the table deciding who talks to whom
+    #[serde(default)]
+    pub layout: TableLayout,
+    /// Shown in exports instead of the table id, not used by solvers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Free-form labels like the room or the kind of table, not used by solvers
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Anything else the source system knows about the table, carried along unchanged
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TableLayout {
+    /// Round table where only people in adjacent seats are neighbours
+    #[default]
+    Ring,
+    /// Small round table where everybody talks to everybody
+    AllPairs,
+    /// Long table with two benches, seats alternate between the sides and
+    /// people are neighbours with the ones next to them and the one opposite
+    Bench,
+}
+
+impl TableLayout {
+    /// Pairs of seats whose occupants get their relation score
+    pub fn neighbour_seats(&self, no_of_people: usize) -> SmallVec<[(usize, usize); 16]> {
+        match self {
+            TableLayout::Ring if no_of_people > 1 => (0..no_of_people)
+                .map(|seat| (seat, (seat + 1) % no_of_people))
+                .collect(),
+            TableLayout::Ring => SmallVec::new(),
+            TableLayout::AllPairs => (0..no_of_people).tuple_combinations().collect(),
+            TableLayout::Bench => (0..no_of_people)
+                .flat_map(|seat| {
+                    let opposite = (seat % 2 == 0).then_some(seat + 1);
+                    let next_on_same_side = seat + 2;
+                    opposite
+                        .into_iter()
+                        .chain(iter::once(next_on_same_side))
+                        .map(move |other_seat| (seat, other_seat))
+                })
+                .filter(|(_, other_seat)| *other_seat < no_of_people)
+                .collect(),
+        }
+    }
+
+    /// Number of seat indexes worth trying when adding a person to a table with given no of people
+    pub fn insertion_positions(&self, no_of_people: usize) -> usize {
+        match self {
+            // Inserting at the end is the same as inserting at the start on a round table
+            TableLayout::Ring => no_of_people.max(1),
+            TableLayout::AllPairs => 1,
+            TableLayout::Bench => no_of_people + 1,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
 pub struct Solution {
     pub solution_per_table: HashMap<TableDayId, Vec<PersonId>>,
+    /// People left without a seat, only allowed for people with an unassigned penalty
+    pub unassigned: Vec<PersonId>,
 }
 
 #[derive(Debug, Clone)]
 pub struct AlgorithmResults {
     pub solution: Solution,
+    /// Objective value of the solution as tracked by the solver
+    pub objective_value: f64,
     pub no_of_iterations: usize,
     pub elapsed: chrono::Duration,
 }
@@ -0,0 +1,149 @@
+//! Problem files carry the version of their format. Loaders upgrade files of older versions
+//! step by step to the current one before reading them into the in-memory model.
+//!
+//! Versions:
+//! 1. No `schema_version` field, the format of every file written before it was introduced
+//! 2. `schema_version` next to the problem fields
+
+use std::fmt;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::problem::ProblemDescription;
+
+pub const SCHEMA_VERSION: u64 = 2;
+
+/// Files without a version field were written before versions were introduced
+const UNVERSIONED: u64 = 1;
+
+/// Upgrades from the version at the index + 1 to the next one
+const MIGRATIONS: [fn(Value) -> Value; (SCHEMA_VERSION - 1) as usize] = [
+    // Nothing changed but the version field, which is set after every migration
+    |value| value,
+];
+
+/// Problem as stored in a file, only used to describe the format
+#[derive(JsonSchema)]
+#[schemars(title = "Problem")]
+pub struct ProblemFile {
+    /// Version of the file format
+    pub schema_version: u64,
+    #[serde(flatten)]
+    pub problem: ProblemDescription,
+}
+
+/// Same fields as ProblemFile, written without taking ownership of the problem. Binary files
+/// use the same envelope.
+#[derive(Serialize)]
+pub(crate) struct ProblemFileRef<'a> {
+    schema_version: u64,
+    #[serde(flatten)]
+    problem: &'a ProblemDescription,
+}
+
+impl<'a> ProblemFileRef<'a> {
+    /// The problem with the current schema version
+    pub(crate) fn new(problem: &'a ProblemDescription) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            problem,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SchemaError {
+    Json(serde_json::Error),
+    NotAnObject,
+    InvalidVersion(Value),
+    /// Written by a newer version of the crate
+    UnsupportedVersion(u64),
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaError::Json(error) => write!(f, "{}", error),
+            SchemaError::NotAnObject => write!(f, "Problem must be a JSON object"),
+            SchemaError::InvalidVersion(version) => {
+                write!(f, "Schema version {} is not a positive integer", version)
+            }
+            SchemaError::UnsupportedVersion(version) => write!(
+                f,
+                "Schema version {} is newer than the supported version {}",
+                version, SCHEMA_VERSION
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+impl From<serde_json::Error> for SchemaError {
+    fn from(error: serde_json::Error) -> Self {
+        SchemaError::Json(error)
+    }
+}
+
+/// Writes the problem with the current schema version
+pub fn problem_to_json(problem: &ProblemDescription) -> Result<String, serde_json::Error> {
+    serde_json::to_string(&ProblemFileRef::new(problem))
+}
+
+/// Only the version of a problem file, every other field is skipped
+#[derive(Deserialize)]
+pub(crate) struct VersionField {
+    schema_version: Option<Value>,
+}
+
+impl VersionField {
+    /// Files of the current version can be read without migrating them
+    pub(crate) fn is_current(&self) -> bool {
+        self.schema_version.as_ref().and_then(|v| v.as_u64()) == Some(SCHEMA_VERSION)
+    }
+}
+
+/// Reads a problem file of any supported version
+pub fn problem_from_json(json: &str) -> Result<ProblemDescription, SchemaError> {
+    // Files of the current version are read directly, without building a JSON tree first
+    let version_field: VersionField = serde_json::from_str(json)?;
+    if version_field.is_current() {
+        return Ok(serde_json::from_str(json)?);
+    }
+    let mut value = migrate(serde_json::from_str(json)?)?;
+    value.as_object_mut().unwrap().remove("schema_version");
+    Ok(serde_json::from_value(value)?)
+}
+
+/// Upgrades a problem file of any supported version to the current one
+pub fn migrate(mut value: Value) -> Result<Value, SchemaError> {
+    let version = match value
+        .as_object()
+        .ok_or(SchemaError::NotAnObject)?
+        .get("schema_version")
+    {
+        None => UNVERSIONED,
+        Some(version) => version
+            .as_u64()
+            .filter(|version| *version >= 1)
+            .ok_or_else(|| SchemaError::InvalidVersion(version.clone()))?,
+    };
+    if version > SCHEMA_VERSION {
+        return Err(SchemaError::UnsupportedVersion(version));
+    }
+    for migration in &MIGRATIONS[(version - 1) as usize..] {
+        value = migration(value);
+    }
+    value
+        .as_object_mut()
+        .ok_or(SchemaError::NotAnObject)?
+        .insert("schema_version".to_string(), SCHEMA_VERSION.into());
+    Ok(value)
+}
+
+/// JSON Schema of the current problem file format
+pub fn problem_json_schema() -> String {
+    serde_json::to_string_pretty(&schemars::schema_for!(ProblemFile)).unwrap()
+}
@@ -1,37 +1,277 @@
-use ahash::HashSet;
+use std::fmt;
 
-use crate::problem::{PersonId, ProblemDescription, Solution, MAX_PEOPLE_FOR_TABLE};
+use ahash::{HashMap, HashSet};
+use chrono::NaiveDate;
 
+use crate::problem::{
+    Person, PersonId, ProblemDescription, Solution, TableDay, TableDayId, MAX_PEOPLE_FOR_TABLE,
+};
+
+/// Panics describing the first reason the solution is not valid for the problem
 pub fn validate_solution(input: &ProblemDescription, solution: &Solution) {
-    assert_eq!(
-        input.tables.len(),
-        solution.solution_per_table.len(),
-        "No of tables in solution must match"
-    );
-
-    assert_eq!(
-        input.people.len(),
-        solution.solution_per_table.values().flatten().count(),
-        "No of people in the solution must be the same"
-    );
-
-    let all_people_from_solution: HashSet<PersonId> = solution
+    if let Err(error) = check_solution(input, solution) {
+        panic!("{}", error);
+    }
+}
+
+/// Reasons a solution is not valid for its problem
+#[derive(Debug, Clone, PartialEq)]
+pub enum SolutionError {
+    WrongNoOfTables {
+        expected: usize,
+        found: usize,
+    },
+    WrongNoOfSeatings {
+        expected: usize,
+        found: usize,
+    },
+    /// Person is in the solution a different number of times than they require seatings,
+    /// or is not in the problem at all
+    WrongSeatingsOfPerson(PersonId),
+    MustBeSeated(PersonId),
+    TooManyPeople(TableDayId),
+    UnknownTable(TableDayId),
+    NotAvailable(PersonId, TableDayId),
+    SeatedTwiceOnDate(PersonId, NaiveDate),
+}
+
+impl fmt::Display for SolutionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolutionError::WrongNoOfTables { expected, found } => write!(
+                f,
+                "No of tables in solution must match, expected {} found {}",
+                expected, found
+            ),
+            SolutionError::WrongNoOfSeatings { expected, found } => write!(
+                f,
+                "No of seatings in the solution must be the same, expected {} found {}",
+                expected, found
+            ),
+            SolutionError::WrongSeatingsOfPerson(person_id) => write!(
+                f,
+                "Person {} is not in the solution the required number of times",
+                person_id
+            ),
+            SolutionError::MustBeSeated(person_id) => {
+                write!(f, "Person {} must be seated", person_id)
+            }
+            SolutionError::TooManyPeople(table_day_id) => {
+                write!(f, "Table {} had more people then allowed", table_day_id)
+            }
+            SolutionError::UnknownTable(table_day_id) => {
+                write!(f, "Solution contains unknown table {}", table_day_id)
+            }
+            SolutionError::NotAvailable(person_id, table_day_id) => write!(
+                f,
+                "Person {} is not available on the date of table {}",
+                person_id, table_day_id
+            ),
+            SolutionError::SeatedTwiceOnDate(person_id, date) => write!(
+                f,
+                "Person {} is seated more than once on {}",
+                person_id, date
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SolutionError {}
+
+pub fn check_solution(
+    input: &ProblemDescription,
+    solution: &Solution,
+) -> Result<(), SolutionError> {
+    if input.tables.len() != solution.solution_per_table.len() {
+        return Err(SolutionError::WrongNoOfTables {
+            expected: input.tables.len(),
+            found: solution.solution_per_table.len(),
+        });
+    }
+
+    let expected_seatings = input
+        .people
+        .iter()
+        .map(|p| p.required_seatings)
+        .sum::<usize>();
+    let found_seatings =
+        solution.solution_per_table.values().flatten().count() + solution.unassigned.len();
+    if expected_seatings != found_seatings {
+        return Err(SolutionError::WrongNoOfSeatings {
+            expected: expected_seatings,
+            found: found_seatings,
+        });
+    }
+
+    let mut seatings_from_solution: HashMap<PersonId, usize> = HashMap::default();
+    for person_id in solution
         .solution_per_table
         .values()
-        .cloned()
         .flatten()
+        .chain(solution.unassigned.iter())
+    {
+        *seatings_from_solution.entry(*person_id).or_default() += 1;
+    }
+    let seatings_from_input: HashMap<PersonId, usize> = input
+        .people
+        .iter()
+        .filter(|p| p.required_seatings > 0)
+        .map(|p| (p.id, p.required_seatings))
         .collect();
-    let all_people_from_input: HashSet<PersonId> = input.people.iter().map(|p| p.id).collect();
-    assert_eq!(
-        all_people_from_input, all_people_from_solution,
-        "all peaople from input shoud be in solution"
-    );
+    if let Some(person_id) = seatings_from_input
+        .keys()
+        .chain(seatings_from_solution.keys())
+        .filter(|person_id| {
+            seatings_from_input.get(person_id) != seatings_from_solution.get(person_id)
+        })
+        .min()
+    {
+        return Err(SolutionError::WrongSeatingsOfPerson(*person_id));
+    }
+
+    let people_map: HashMap<PersonId, &Person> = input.people.iter().map(|p| (p.id, p)).collect();
+    let table_map: HashMap<TableDayId, &TableDay> =
+        input.tables.iter().map(|t| (t.id, t)).collect();
+    let mut seated_dates: HashSet<(PersonId, NaiveDate)> = HashSet::default();
+
+    for person_id in solution.unassigned.iter() {
+        if people_map[person_id].unassigned_penalty.is_none() {
+            return Err(SolutionError::MustBeSeated(*person_id));
+        }
+    }
 
     for (table_day_id, people_for_table) in solution.solution_per_table.iter() {
-        assert!(
-            people_for_table.len() <= MAX_PEOPLE_FOR_TABLE,
-            "Table {} had more people then allowed",
-            table_day_id
-        )
+        if people_for_table.len() > MAX_PEOPLE_FOR_TABLE {
+            return Err(SolutionError::TooManyPeople(*table_day_id));
+        }
+
+        let table = table_map
+            .get(table_day_id)
+            .ok_or(SolutionError::UnknownTable(*table_day_id))?;
+        for person_id in people_for_table {
+            if !people_map[person_id].is_available_on(table.date) {
+                return Err(SolutionError::NotAvailable(*person_id, *table_day_id));
+            }
+            if !seated_dates.insert((*person_id, table.date.date_naive())) {
+                return Err(SolutionError::SeatedTwiceOnDate(
+                    *person_id,
+                    table.date.date_naive(),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Reasons a problem can not be solved, found before running any solver
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProblemError {
+    DuplicatePerson(PersonId),
+    DuplicateTableDay(TableDayId),
+    NotFiniteRelation(PersonId, PersonId),
+    NotFinitePenalty(PersonId),
+    /// Person must be seated more times than there are dates they are available on
+    TooManySeatings(PersonId),
+    NotEnoughSeats {
+        required: usize,
+        available: usize,
+    },
+}
+
+impl fmt::Display for ProblemError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProblemError::DuplicatePerson(person_id) => {
+                write!(f, "Person {} is defined more than once", person_id)
+            }
+            ProblemError::DuplicateTableDay(table_day_id) => {
+                write!(f, "Table {} is defined more than once", table_day_id)
+            }
+            ProblemError::NotFiniteRelation(person_1_id, person_2_id) => write!(
+                f,
+                "Relation of {} to {} is not a finite number",
+                person_1_id, person_2_id
+            ),
+            ProblemError::NotFinitePenalty(person_id) => write!(
+                f,
+                "Unassigned penalty of person {} is not a finite number",
+                person_id
+            ),
+            ProblemError::TooManySeatings(person_id) => write!(
+                f,
+                "Person {} must be seated more times than there are dates they are available on",
+                person_id
+            ),
+            ProblemError::NotEnoughSeats {
+                required,
+                available,
+            } => write!(
+                f,
+                "{} seatings must be assigned, but there are only {} seats",
+                required, available
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ProblemError {}
+
+/// Checks what solvers take for granted. Passing does not guarantee a solver finds
+/// a seat for everybody, availability can still make the problem infeasible.
+pub fn validate_problem(input: &ProblemDescription) -> Result<(), ProblemError> {
+    let mut person_ids: HashSet<PersonId> = HashSet::default();
+    for person in input.people.iter() {
+        if !person_ids.insert(person.id) {
+            return Err(ProblemError::DuplicatePerson(person.id));
+        }
+    }
+    let mut table_day_ids: HashSet<TableDayId> = HashSet::default();
+    for table in input.tables.iter() {
+        if !table_day_ids.insert(table.id) {
+            return Err(ProblemError::DuplicateTableDay(table.id));
+        }
+    }
+
+    for (person_1_id, relations) in input.people_relations.iter() {
+        for (person_2_id, value) in relations.iter() {
+            if !value.is_finite() {
+                return Err(ProblemError::NotFiniteRelation(*person_1_id, *person_2_id));
+            }
+        }
+    }
+
+    let dates: HashSet<NaiveDate> = input.tables.iter().map(|t| t.date.date_naive()).collect();
+    for person in input.people.iter() {
+        if person
+            .unassigned_penalty
+            .is_some_and(|penalty| !penalty.is_finite())
+        {
+            return Err(ProblemError::NotFinitePenalty(person.id));
+        }
+        let no_of_available_dates = match &person.available_dates {
+            Some(available_dates) => available_dates
+                .iter()
+                .filter(|date| dates.contains(*date))
+                .count(),
+            None => dates.len(),
+        };
+        if person.required_seatings > no_of_available_dates {
+            return Err(ProblemError::TooManySeatings(person.id));
+        }
+    }
+
+    let required = input
+        .people
+        .iter()
+        .filter(|p| p.unassigned_penalty.is_none())
+        .map(|p| p.required_seatings)
+        .sum::<usize>();
+    let available = input.tables.len() * MAX_PEOPLE_FOR_TABLE;
+    if required > available {
+        return Err(ProblemError::NotEnoughSeats {
+            required,
+            available,
+        });
     }
+    Ok(())
 }
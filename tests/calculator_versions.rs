@@ -0,0 +1,301 @@
+//! Every calculator version must compute the same objective as v1
+
+use itertools::Itertools;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use table_problem::generator::generate_problem_with_rng;
+use table_problem::objective_value_calculator::{v1, v2, v3, v4, v5, v6, v7};
+use table_problem::problem::{
+    PersonId, PersonVisit, ProblemDescription, RelationSemantics, Solution, TableDayId,
+    TableLayout, MAX_PEOPLE_FOR_TABLE,
+};
+
+const EPSILON: f64 = 1e-9;
+
+struct AllCalculators<'a> {
+    v1: v1::ObjectiveValueCalculator<'a>,
+    v2: v2::ObjectiveValueCalculator<'a>,
+    v3: v3::ObjectiveValueCalculator<'a>,
+    v4: v4::ObjectiveValueCalculator<'a>,
+    v5: v5::ObjectiveValueCalculator,
+    v6: v6::ObjectiveValueCalculator,
+    v7: v7::ObjectiveValueCalculator,
+}
+
+impl<'a> AllCalculators<'a> {
+    fn new(problem: &'a ProblemDescription) -> Self {
+        Self {
+            v1: v1::ObjectiveValueCalculator::new(problem),
+            v2: v2::ObjectiveValueCalculator::new(problem),
+            v3: v3::ObjectiveValueCalculator::new(problem),
+            v4: v4::ObjectiveValueCalculator::new(problem),
+            v5: v5::ObjectiveValueCalculator::new(problem),
+            v6: v6::ObjectiveValueCalculator::new(problem),
+            v7: v7::ObjectiveValueCalculator::new(problem),
+        }
+    }
+
+    fn assert_table_values_agree(&self, table_day_id: TableDayId, people: &[PersonId]) {
+        assert_agree(
+            [
+                self.v1.table_value(table_day_id, people),
+                self.v2.table_value(table_day_id, people),
+                self.v3.table_value(table_day_id, people),
+                self.v4.table_value(table_day_id, people),
+                self.v5.table_value(table_day_id, people),
+                self.v6.table_value(table_day_id, people),
+                self.v7.table_value(table_day_id, people),
+            ],
+            || format!("table {} with {:?}", table_day_id, people),
+        );
+    }
+
+    fn assert_solution_values_agree(&self, solution: &Solution) {
+        assert_agree(
+            [
+                self.v1.solution_value(solution),
+                self.v2.solution_value(solution),
+                self.v3.solution_value(solution),
+                self.v4.solution_value(solution),
+                self.v5.solution_value(solution),
+                self.v6.solution_value(solution),
+                self.v7.solution_value(solution),
+            ],
+            || format!("{:?}", solution),
+        );
+    }
+}
+
+fn assert_agree(values: [f64; 7], context: impl Fn() -> String) {
+    for (version, value) in values.iter().enumerate().skip(1) {
+        assert!(
+            (value - values[0]).abs() < EPSILON,
+            "v{} computed {} instead of {} for {}",
+            version + 1,
+            value,
+            values[0],
+            context()
+        );
+    }
+}
+
+fn random_problem(seed: u64, no_of_people: usize, no_of_table_days: usize) -> ProblemDescription {
+    generate_problem_with_rng(
+        no_of_people,
+        no_of_table_days,
+        &mut StdRng::seed_from_u64(seed),
+    )
+}
+
+/// Deals shuffled people into tables of random size, whoever is left is unassigned
+fn random_solution(problem: &ProblemDescription, rng: &mut impl Rng) -> Solution {
+    let mut people = problem.people.iter().map(|p| p.id).collect_vec();
+    people.shuffle(rng);
+    let mut people = people.into_iter();
+    Solution {
+        solution_per_table: problem
+            .tables
+            .iter()
+            .map(|t| {
+                (
+                    t.id,
+                    people
+                        .by_ref()
+                        .take(rng.gen_range(0..=MAX_PEOPLE_FOR_TABLE))
+                        .collect(),
+                )
+            })
+            .collect(),
+        unassigned: people.collect(),
+    }
+}
+
+fn with_unassigned_penalties(mut problem: ProblemDescription) -> ProblemDescription {
+    for (index, person) in problem.people.iter_mut().enumerate() {
+        person.unassigned_penalty = Some(index as f64 / 10.0);
+    }
+    problem
+}
+
+fn assert_random_tables_agree(problem: &ProblemDescription, seed: u64) {
+    let calculators = AllCalculators::new(problem);
+    let mut rng = StdRng::seed_from_u64(seed);
+    let people_ids = problem.people.iter().map(|p| p.id).collect_vec();
+    for table in problem.tables.iter() {
+        for no_of_people in 0..=MAX_PEOPLE_FOR_TABLE {
+            let people = people_ids
+                .choose_multiple(&mut rng, no_of_people)
+                .cloned()
+                .collect_vec();
+            calculators.assert_table_values_agree(table.id, &people);
+        }
+    }
+}
+
+fn assert_random_solutions_agree(problem: &ProblemDescription, seed: u64) {
+    let calculators = AllCalculators::new(problem);
+    let mut rng = StdRng::seed_from_u64(seed);
+    for _ in 0..20 {
+        calculators.assert_solution_values_agree(&random_solution(problem, &mut rng));
+    }
+}
+
+#[test]
+fn table_values_agree_on_random_problems() {
+    for seed in 0..5 {
+        assert_random_tables_agree(&random_problem(seed, 120, 40), seed);
+    }
+}
+
+#[test]
+fn solution_values_agree_on_random_problems() {
+    for seed in 0..5 {
+        assert_random_solutions_agree(
+            &with_unassigned_penalties(random_problem(seed, 120, 15)),
+            seed,
+        );
+    }
+}
+
+#[test]
+fn single_person_tables_agree() {
+    let problem = random_problem(1, 60, 30);
+    let calculators = AllCalculators::new(&problem);
+    for table in problem.tables.iter() {
+        for person in problem.people.iter() {
+            calculators.assert_table_values_agree(table.id, &[person.id]);
+        }
+    }
+    calculators.assert_solution_values_agree(&Solution {
+        solution_per_table: problem
+            .tables
+            .iter()
+            .zip(problem.people.iter())
+            .map(|(t, p)| (t.id, vec![p.id]))
+            .collect(),
+        unassigned: vec![],
+    });
+}
+
+#[test]
+fn people_without_visits_agree() {
+    let mut problem = random_problem(2, 80, 20);
+    for person in problem.people.iter_mut() {
+        person.visits.clear();
+    }
+    assert_random_tables_agree(&problem, 2);
+    assert_random_solutions_agree(&with_unassigned_penalties(problem), 2);
+}
+
+#[test]
+fn people_without_relations_agree() {
+    let mut problem = random_problem(3, 80, 20);
+    problem.people_relations.clear();
+    assert_random_tables_agree(&problem, 3);
+}
+
+#[test]
+fn directed_relations_agree() {
+    let mut problem = random_problem(4, 80, 20);
+    problem.relation_semantics = RelationSemantics::Directed;
+    // Add the reverse direction for some pairs, so both directions count
+    let reversed = problem
+        .people_relations
+        .iter()
+        .flat_map(|(p1, r)| r.iter().map(move |(p2, value)| (*p2, *p1, *value)))
+        .step_by(3)
+        .collect_vec();
+    for (p1, p2, value) in reversed {
+        problem
+            .people_relations
+            .entry(p1)
+            .or_default()
+            .insert(p2, -value);
+    }
+    assert_random_tables_agree(&problem, 4);
+    assert_random_solutions_agree(&with_unassigned_penalties(problem), 4);
+}
+
+#[test]
+fn table_layouts_agree() {
+    let mut problem = random_problem(5, 80, 21);
+    for (index, table) in problem.tables.iter_mut().enumerate() {
+        table.layout = [TableLayout::Ring, TableLayout::AllPairs, TableLayout::Bench][index % 3];
+    }
+    assert_random_tables_agree(&problem, 5);
+    assert_random_solutions_agree(&with_unassigned_penalties(problem), 5);
+}
+
+#[test]
+fn repeated_neighbours_agree() {
+    let mut problem = random_problem(6, 30, 20);
+    for person in problem.people.iter_mut() {
+        person.required_seatings = 3;
+    }
+    let calculators = AllCalculators::new(&problem);
+    let mut rng = StdRng::seed_from_u64(6);
+    let people_ids = problem.people.iter().map(|p| p.id).collect_vec();
+    for _ in 0..20 {
+        // Few people over many tables, so the same neighbours meet again
+        let solution = Solution {
+            solution_per_table: problem
+                .tables
+                .iter()
+                .map(|t| {
+                    let no_of_people = rng.gen_range(0..=MAX_PEOPLE_FOR_TABLE);
+                    (
+                        t.id,
+                        people_ids
+                            .choose_multiple(&mut rng, no_of_people)
+                            .cloned()
+                            .collect(),
+                    )
+                })
+                .collect(),
+            unassigned: vec![],
+        };
+        calculators.assert_solution_values_agree(&solution);
+    }
+}
+
+#[test]
+fn many_people_agree() {
+    // Enough people for v7 to keep relations sparse, most of them without relations
+    // to keep the test fast
+    let mut problem = random_problem(7, 2100, 5);
+    problem
+        .people_relations
+        .retain(|person_id, _| *person_id < 100);
+    assert_random_tables_agree(&problem, 7);
+}
+
+#[test]
+fn recency_grows_with_days_since_last_visit() {
+    let mut problem = random_problem(7, 20, 8);
+    let first_date = problem.tables.iter().map(|t| t.date).min().unwrap();
+    // Last visit 5, 20 and 40 days before the table
+    let expected = [(5, -2.0 / 3.0), (20, 1.0 / 3.0), (40, 1.0)];
+    problem.people[0].visits = vec![PersonVisit {
+        table_id: problem.tables[0].table_id,
+        at: first_date - chrono::Duration::days(40),
+    }];
+    for (table, (days, _)) in problem.tables.iter_mut().zip(expected) {
+        table.date = first_date - chrono::Duration::days(40 - days);
+    }
+    let v1 = v1::ObjectiveValueCalculator::new(&problem);
+    for (table, (_, recency)) in problem.tables.iter().zip(expected) {
+        let [value, _, _] = v1.person_score(problem.people[0].id, table.id);
+        assert!(
+            (value - recency).abs() < EPSILON,
+            "{} != {}",
+            value,
+            recency
+        );
+    }
+    let calculators = AllCalculators::new(&problem);
+    for table in problem.tables.iter().take(expected.len()) {
+        calculators.assert_table_values_agree(table.id, &[problem.people[0].id]);
+    }
+}
@@ -0,0 +1,197 @@
+//! Subcommands of the binary and their exit codes
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use table_problem::objective_value_calculator::v1::ObjectiveValueCalculator;
+use table_problem::problem::{ProblemDescription, Solution};
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_rust-algorithm-optimization"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn read<T: serde::de::DeserializeOwned>(path: &Path) -> T {
+    serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+/// Fresh dir with two generated problems and their solutions
+fn solved_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    let problems = dir.join("problems");
+    let solutions = dir.join("solutions");
+    let generated = run(&[
+        "generate",
+        "-o",
+        problems.to_str().unwrap(),
+        "-n",
+        "2",
+        "--size",
+        "small",
+        "--seed",
+        "1",
+    ]);
+    assert!(generated.status.success());
+    let solved = run(&[
+        "solve",
+        "-i",
+        problems.to_str().unwrap(),
+        "--time-limit-ms",
+        "20",
+        "--seed",
+        "1",
+        "-o",
+        solutions.to_str().unwrap(),
+    ]);
+    assert!(solved.status.success());
+    dir
+}
+
+#[test]
+fn solved_problems_validate_and_score() {
+    let dir = solved_dir("cli_test_solve");
+    let problem = dir.join("problems/p_0.json");
+    let solution = dir.join("solutions/p_0.solution.json");
+
+    let validated = run(&[
+        "validate",
+        "-p",
+        problem.to_str().unwrap(),
+        "-s",
+        solution.to_str().unwrap(),
+    ]);
+    assert!(validated.status.success());
+
+    let problem_description: ProblemDescription = read(&problem);
+    let expected =
+        ObjectiveValueCalculator::new(&problem_description)
+            .solution_value(&read::<Solution>(&solution));
+    for calculator in ["v1", "v7"] {
+        let scored = run(&[
+            "score",
+            "-p",
+            problem.to_str().unwrap(),
+            "-s",
+            solution.to_str().unwrap(),
+            "--calculator",
+            calculator,
+        ]);
+        assert!(scored.status.success());
+        let value: f64 = String::from_utf8(scored.stdout)
+            .unwrap()
+            .trim()
+            .parse()
+            .unwrap();
+        assert!((value - expected).abs() < 1e-9);
+    }
+
+    let explained = run(&[
+        "explain",
+        "-p",
+        problem.to_str().unwrap(),
+        "-s",
+        solution.to_str().unwrap(),
+    ]);
+    assert!(explained.status.success());
+    assert!(String::from_utf8(explained.stdout)
+        .unwrap()
+        .starts_with(&format!("Objective value {:.3}", expected)));
+}
+
+#[test]
+fn failures_exit_with_non_zero_code() {
+    let dir = solved_dir("cli_test_failures");
+    let problem = dir.join("problems/p_0.json");
+    // Solution of the other problem
+    let wrong_solution = dir.join("solutions/p_1.solution.json");
+    let missing = dir.join("missing.json");
+
+    for args in [
+        vec![
+            "validate",
+            "-p",
+            problem.to_str().unwrap(),
+            "-s",
+            wrong_solution.to_str().unwrap(),
+        ],
+        vec![
+            "score",
+            "-p",
+            problem.to_str().unwrap(),
+            "-s",
+            wrong_solution.to_str().unwrap(),
+        ],
+        vec![
+            "explain",
+            "-p",
+            problem.to_str().unwrap(),
+            "-s",
+            wrong_solution.to_str().unwrap(),
+        ],
+        vec!["validate", "-p", missing.to_str().unwrap()],
+        vec!["solve", "-i", missing.to_str().unwrap()],
+        vec!["solve", "-i", problem.to_str().unwrap(), "--solver", "v0"],
+        vec![
+            "generate",
+            "-o",
+            dir.join("negative_slack").to_str().unwrap(),
+            "--capacity-slack-percent=-50",
+        ],
+    ] {
+        let output = run(&args);
+        assert!(!output.status.success(), "{:?} succeeded", args);
+    }
+}
+
+#[test]
+fn binary_files_are_told_by_extension() {
+    let dir = solved_dir("cli_test_binary");
+    let binary_problems = dir.join("binary_problems");
+    let binary_solutions = dir.join("binary_solutions");
+    std::fs::create_dir_all(&binary_problems).unwrap();
+    let problem = binary_problems.join("p_0.msgpack");
+    let converted = run(&[
+        "convert",
+        "-i",
+        dir.join("problems/p_0.json").to_str().unwrap(),
+        "-o",
+        problem.to_str().unwrap(),
+    ]);
+    assert!(converted.status.success());
+
+    let solved = run(&[
+        "solve",
+        "-i",
+        binary_problems.to_str().unwrap(),
+        "--time-limit-ms",
+        "20",
+        "-o",
+        binary_solutions.to_str().unwrap(),
+    ]);
+    assert!(solved.status.success());
+    let solution = binary_solutions.join("p_0.solution.msgpack");
+    for (problem, solution) in [
+        (&problem, &solution),
+        (&dir.join("problems/p_0.json"), &solution),
+        (&problem, &dir.join("solutions/p_0.solution.json")),
+    ] {
+        let validated = run(&[
+            "validate",
+            "-p",
+            problem.to_str().unwrap(),
+            "-s",
+            solution.to_str().unwrap(),
+        ]);
+        assert!(validated.status.success());
+    }
+
+    // JSON in a binary file
+    let wrong = dir.join("wrong.msgpack");
+    std::fs::copy(dir.join("problems/p_0.json"), &wrong).unwrap();
+    assert!(!run(&["validate", "-p", wrong.to_str().unwrap()])
+        .status
+        .success());
+}
@@ -0,0 +1,168 @@
+//! Problems read from CSV exports and the errors reported for bad rows
+
+use std::path::{Path, PathBuf};
+
+use chrono::{NaiveDate, TimeZone, Utc};
+
+use table_problem::import::{import_csv_dir, ImportError};
+use table_problem::problem::{RelationSemantics, TableLayout};
+use table_problem::validator::validate_problem;
+
+const PEOPLE: &str = "id,unassigned_penalty,required_seatings,available_dates\n1,,,\n2,,,\n";
+const TABLES: &str = "id,table_id,date\n0,0,2024-03-04\n";
+
+/// Fresh dir with the given files
+fn csv_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    for (file, content) in files {
+        std::fs::write(dir.join(file), content).unwrap();
+    }
+    dir
+}
+
+fn import_error(name: &str, files: &[(&str, &str)]) -> ImportError {
+    import_csv_dir(&csv_dir(name, files), RelationSemantics::Symmetric).unwrap_err()
+}
+
+#[test]
+fn example_files_are_imported() {
+    let problem = import_csv_dir(Path::new("examples/csv"), RelationSemantics::Symmetric).unwrap();
+    validate_problem(&problem).unwrap();
+    assert_eq!(problem.people.len(), 7);
+    assert_eq!(problem.tables.len(), 3);
+
+    let person_3 = problem.people.iter().find(|p| p.id == 3).unwrap();
+    assert_eq!(person_3.required_seatings, 2);
+    assert_eq!(person_3.visits.len(), 2);
+    assert_eq!(
+        person_3.visits[0].at,
+        Utc.with_ymd_and_hms(2024, 2, 26, 18, 30, 0).unwrap()
+    );
+    let person_4 = problem.people.iter().find(|p| p.id == 4).unwrap();
+    assert_eq!(person_4.unassigned_penalty, Some(0.5));
+    assert_eq!(
+        person_4
+            .available_dates
+            .as_ref()
+            .unwrap()
+            .iter()
+            .cloned()
+            .collect::<Vec<_>>(),
+        vec![
+            NaiveDate::from_ymd_opt(2024, 3, 4).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 3, 5).unwrap()
+        ]
+    );
+
+    let person_2 = problem.people.iter().find(|p| p.id == 2).unwrap();
+    assert_eq!(person_2.name.as_deref(), Some("Alan Turing"));
+    assert_eq!(person_2.email.as_deref(), Some("alan@example.com"));
+    assert_eq!(person_2.tags, vec!["speaker", "vegetarian"]);
+    assert_eq!(person_2.metadata["department"], "Research");
+    assert!(person_4.metadata.is_empty());
+    assert_eq!(person_4.email, None);
+
+    assert_eq!(problem.tables[1].name.as_deref(), Some("Long table"));
+    assert_eq!(problem.tables[1].tags, vec!["accessible"]);
+    assert_eq!(problem.tables[1].metadata["room"], "Main hall");
+    assert_eq!(problem.tables[1].layout, TableLayout::Bench);
+    // Empty layout is the default
+    assert_eq!(problem.tables[2].layout, TableLayout::Ring);
+    assert_eq!(problem.people_relations[&2][&3], -0.5);
+}
+
+#[test]
+fn visits_and_relations_are_optional() {
+    let dir = csv_dir(
+        "csv_import_minimal",
+        &[("people.csv", PEOPLE), ("tables.csv", TABLES)],
+    );
+    let problem = import_csv_dir(&dir, RelationSemantics::Directed).unwrap();
+    assert_eq!(problem.people.len(), 2);
+    assert!(problem.people_relations.is_empty());
+    assert_eq!(problem.relation_semantics, RelationSemantics::Directed);
+}
+
+#[test]
+fn bad_rows_are_reported_with_line_numbers() {
+    let cases = [
+        (
+            vec![("people.csv", "id\n1\nx\n"), ("tables.csv", TABLES)],
+            "people.csv line 3: id 'x': invalid digit found in string",
+        ),
+        (
+            vec![("people.csv", "id\n1\n1\n"), ("tables.csv", TABLES)],
+            "people.csv line 3: person 1 is defined more than once",
+        ),
+        (
+            vec![
+                (
+                    "people.csv",
+                    "id,available_dates\n1,2024-03-04;2024-02-30\n",
+                ),
+                ("tables.csv", TABLES),
+            ],
+            "people.csv line 2: available_dates '2024-03-04;2024-02-30': \
+             '2024-02-30' is not a YYYY-MM-DD date",
+        ),
+        (
+            vec![
+                ("people.csv", PEOPLE),
+                ("tables.csv", "id,table_id,date\n0,0,2024-03-04\n1,0,\n"),
+            ],
+            "tables.csv line 3: date is missing",
+        ),
+        (
+            vec![
+                ("people.csv", PEOPLE),
+                (
+                    "tables.csv",
+                    "id,table_id,date,layout\n0,0,2024-03-04,square\n",
+                ),
+            ],
+            "tables.csv line 2: layout 'square': not one of ring, all_pairs and bench",
+        ),
+        (
+            vec![
+                ("people.csv", PEOPLE),
+                ("tables.csv", TABLES),
+                (
+                    "visits.csv",
+                    "person_id,table_id,at\n1,0,2024-01-01\n3,0,2024-01-01\n",
+                ),
+            ],
+            "visits.csv line 3: unknown person 3",
+        ),
+        (
+            vec![
+                ("people.csv", PEOPLE),
+                ("tables.csv", TABLES),
+                ("relations.csv", "person_1_id,person_2_id,score\n1,2,NaN\n"),
+            ],
+            "relations.csv line 2: score is not a finite number",
+        ),
+        (
+            vec![("tables.csv", TABLES)],
+            "people.csv: No such file or directory (os error 2)",
+        ),
+    ];
+    for (index, (files, message)) in cases.iter().enumerate() {
+        let error = import_error(&format!("csv_import_error_{}", index), files);
+        assert_eq!(error.to_string(), *message);
+    }
+}
+
+#[test]
+fn rows_with_wrong_number_of_fields_are_reported() {
+    let error = import_error(
+        "csv_import_wrong_length",
+        &[
+            ("people.csv", "id,required_seatings\n1,1\n2,1,5\n"),
+            ("tables.csv", TABLES),
+        ],
+    );
+    assert_eq!(error.file, "people.csv");
+    assert_eq!(error.line, Some(3));
+}
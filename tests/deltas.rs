@@ -0,0 +1,227 @@
+//! Delta evaluation of v6 against the full table values before and after the change, on every
+//! layout with empty, single person and full tables and every seat
+
+use itertools::Itertools;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+use table_problem::generator::generate_problem_with_rng;
+use table_problem::objective_value_calculator::v6::ObjectiveValueCalculator;
+use table_problem::problem::{
+    PersonId, ProblemDescription, RelationSemantics, TableDayId, TableLayout, MAX_PEOPLE_FOR_TABLE,
+};
+
+const EPSILON: f64 = 1e-9;
+
+const LAYOUTS: [TableLayout; 3] = [TableLayout::Ring, TableLayout::AllPairs, TableLayout::Bench];
+
+fn problem(layout: TableLayout, relation_semantics: RelationSemantics) -> ProblemDescription {
+    let mut problem = generate_problem_with_rng(40, 10, &mut StdRng::seed_from_u64(0));
+    for table in problem.tables.iter_mut() {
+        table.layout = layout;
+    }
+    problem.relation_semantics = relation_semantics;
+    problem
+}
+
+fn problems() -> impl Iterator<Item = ProblemDescription> {
+    LAYOUTS.into_iter().flat_map(|layout| {
+        [RelationSemantics::Symmetric, RelationSemantics::Directed]
+            .map(|relation_semantics| problem(layout, relation_semantics))
+    })
+}
+
+/// Different people for two tables of the given sizes
+fn two_tables(
+    problem: &ProblemDescription,
+    no_of_people_1: usize,
+    no_of_people_2: usize,
+) -> (Vec<PersonId>, Vec<PersonId>) {
+    let mut people_ids = problem.people.iter().map(|p| p.id).collect_vec();
+    people_ids.shuffle(&mut StdRng::seed_from_u64(
+        (no_of_people_1 * 10 + no_of_people_2) as u64,
+    ));
+    let people_ids_2 = people_ids.split_off(no_of_people_1);
+    (people_ids, people_ids_2[..no_of_people_2].to_vec())
+}
+
+fn assert_delta(delta: f64, expected: f64, context: impl Fn() -> String) {
+    assert!(
+        (delta - expected).abs() < EPSILON,
+        "{}: delta {} instead of {}",
+        context(),
+        delta,
+        expected
+    );
+}
+
+/// Change of the values of both tables
+fn values_change(
+    calculator: &ObjectiveValueCalculator,
+    (table_day_id_1, before_1, after_1): (TableDayId, &[PersonId], &[PersonId]),
+    (table_day_id_2, before_2, after_2): (TableDayId, &[PersonId], &[PersonId]),
+) -> f64 {
+    calculator.table_value(table_day_id_1, after_1)
+        + calculator.table_value(table_day_id_2, after_2)
+        - calculator.table_value(table_day_id_1, before_1)
+        - calculator.table_value(table_day_id_2, before_2)
+}
+
+#[test]
+fn insertion_and_removal_deltas_match_table_values() {
+    for problem in problems() {
+        let calculator = ObjectiveValueCalculator::new(&problem);
+        let table_day_id = problem.tables[0].id;
+        for no_of_people in 0..=MAX_PEOPLE_FOR_TABLE {
+            let (people_ids, others) = two_tables(&problem, no_of_people, 1);
+            let before = calculator.table_value(table_day_id, &people_ids);
+            let context = |operation: &str, seat: usize| {
+                format!(
+                    "{} at seat {} of {:?} with {} people",
+                    operation, seat, problem.tables[0].layout, no_of_people
+                )
+            };
+
+            if no_of_people < MAX_PEOPLE_FOR_TABLE {
+                for seat in 0..=no_of_people {
+                    let mut after = people_ids.clone();
+                    after.insert(seat, others[0]);
+                    assert_delta(
+                        calculator.insertion_delta(table_day_id, &people_ids, seat, others[0]),
+                        calculator.table_value(table_day_id, &after) - before,
+                        || context("insertion", seat),
+                    );
+                }
+            }
+            for seat in 0..no_of_people {
+                let mut after = people_ids.clone();
+                after.remove(seat);
+                assert_delta(
+                    calculator.removal_delta(table_day_id, &people_ids, seat),
+                    calculator.table_value(table_day_id, &after) - before,
+                    || context("removal", seat),
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn swap_deltas_match_table_values() {
+    for problem in problems() {
+        let calculator = ObjectiveValueCalculator::new(&problem);
+        let layout = problem.tables[0].layout;
+        let (table_day_id_1, table_day_id_2) = (problem.tables[0].id, problem.tables[1].id);
+        for no_of_people_1 in 1..=MAX_PEOPLE_FOR_TABLE {
+            // Within one table
+            let (people_ids, _) = two_tables(&problem, no_of_people_1, 0);
+            for (seat_1, seat_2) in (0..no_of_people_1).cartesian_product(0..no_of_people_1) {
+                let mut after = people_ids.clone();
+                after.swap(seat_1, seat_2);
+                assert_delta(
+                    calculator.swap_delta(
+                        (table_day_id_1, &people_ids, seat_1),
+                        (table_day_id_1, &people_ids, seat_2),
+                    ),
+                    calculator.table_value(table_day_id_1, &after)
+                        - calculator.table_value(table_day_id_1, &people_ids),
+                    || {
+                        format!(
+                            "swap of seats {} and {} of {:?} with {} people",
+                            seat_1, seat_2, layout, no_of_people_1
+                        )
+                    },
+                );
+            }
+
+            // Across two tables
+            for no_of_people_2 in 1..=MAX_PEOPLE_FOR_TABLE {
+                let (people_ids_1, people_ids_2) =
+                    two_tables(&problem, no_of_people_1, no_of_people_2);
+                for (seat_1, seat_2) in (0..no_of_people_1).cartesian_product(0..no_of_people_2) {
+                    let (mut after_1, mut after_2) = (people_ids_1.clone(), people_ids_2.clone());
+                    std::mem::swap(&mut after_1[seat_1], &mut after_2[seat_2]);
+                    assert_delta(
+                        calculator.swap_delta(
+                            (table_day_id_1, &people_ids_1, seat_1),
+                            (table_day_id_2, &people_ids_2, seat_2),
+                        ),
+                        values_change(
+                            &calculator,
+                            (table_day_id_1, &people_ids_1, &after_1),
+                            (table_day_id_2, &people_ids_2, &after_2),
+                        ),
+                        || {
+                            format!(
+                                "swap of seat {} of {} and seat {} of {} people at {:?} tables",
+                                seat_1, no_of_people_1, seat_2, no_of_people_2, layout
+                            )
+                        },
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn move_deltas_match_table_values() {
+    for problem in problems() {
+        let calculator = ObjectiveValueCalculator::new(&problem);
+        let layout = problem.tables[0].layout;
+        let (table_day_id_1, table_day_id_2) = (problem.tables[0].id, problem.tables[1].id);
+        for no_of_people_1 in 1..=MAX_PEOPLE_FOR_TABLE {
+            // Within one table, the target seat counts without the moved person
+            let (people_ids, _) = two_tables(&problem, no_of_people_1, 0);
+            for (from_seat, to_seat) in (0..no_of_people_1).cartesian_product(0..no_of_people_1) {
+                let mut after = people_ids.clone();
+                let person_id = after.remove(from_seat);
+                after.insert(to_seat, person_id);
+                assert_delta(
+                    calculator.move_delta(
+                        (table_day_id_1, &people_ids, from_seat),
+                        (table_day_id_1, &people_ids, to_seat),
+                    ),
+                    calculator.table_value(table_day_id_1, &after)
+                        - calculator.table_value(table_day_id_1, &people_ids),
+                    || {
+                        format!(
+                            "move from seat {} to {} of {:?} with {} people",
+                            from_seat, to_seat, layout, no_of_people_1
+                        )
+                    },
+                );
+            }
+
+            // To another table, which may be empty but not full
+            for no_of_people_2 in 0..MAX_PEOPLE_FOR_TABLE {
+                let (people_ids_1, people_ids_2) =
+                    two_tables(&problem, no_of_people_1, no_of_people_2);
+                for (from_seat, to_seat) in
+                    (0..no_of_people_1).cartesian_product(0..=no_of_people_2)
+                {
+                    let (mut after_1, mut after_2) = (people_ids_1.clone(), people_ids_2.clone());
+                    after_2.insert(to_seat, after_1.remove(from_seat));
+                    assert_delta(
+                        calculator.move_delta(
+                            (table_day_id_1, &people_ids_1, from_seat),
+                            (table_day_id_2, &people_ids_2, to_seat),
+                        ),
+                        values_change(
+                            &calculator,
+                            (table_day_id_1, &people_ids_1, &after_1),
+                            (table_day_id_2, &people_ids_2, &after_2),
+                        ),
+                        || {
+                            format!(
+                                "move from seat {} of {} to seat {} of {} people at {:?} tables",
+                                from_seat, no_of_people_1, to_seat, no_of_people_2, layout
+                            )
+                        },
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,252 @@
+//! Statistics and reports of solver experiments
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use table_problem::experiment::{
+    csv_report, markdown_report, run_experiment, summarize, ExperimentProblem, ExperimentRun,
+    RunResult, Statistics,
+};
+use table_problem::generator::profile::GeneratorProfile;
+use table_problem::generator::{generate_problem_with_groups, generate_problem_with_rng};
+
+const EPSILON: f64 = 1e-9;
+
+fn run(solver: &str, seed: u64, objective: f64) -> ExperimentRun {
+    ExperimentRun {
+        solver: solver.to_string(),
+        time_limit_ms: 100,
+        problem: "p_0.json".to_string(),
+        seed,
+        result: Ok(RunResult {
+            objective,
+            no_of_iterations: 10 * seed as usize,
+            elapsed_ms: 100.0,
+            same_group_pair_share: None,
+        }),
+    }
+}
+
+fn failed_run(solver: &str, seed: u64) -> ExperimentRun {
+    ExperimentRun {
+        result: Err("Invalid problem: Person 3 is defined more than once".to_string()),
+        ..run(solver, seed, 0.0)
+    }
+}
+
+#[test]
+fn statistics_of_odd_and_even_numbers_of_values() {
+    let statistics = Statistics::new(&[4.0, 1.0, 7.0]);
+    assert!((statistics.mean - 4.0).abs() < EPSILON);
+    assert!((statistics.median - 4.0).abs() < EPSILON);
+    assert!((statistics.std - 6.0_f64.sqrt()).abs() < EPSILON);
+
+    let statistics = Statistics::new(&[4.0, 1.0, 7.0, 2.0]);
+    assert!((statistics.mean - 3.5).abs() < EPSILON);
+    assert!((statistics.median - 3.0).abs() < EPSILON);
+    assert!((statistics.std - 5.25_f64.sqrt()).abs() < EPSILON);
+}
+
+#[test]
+fn statistics_of_a_single_run_and_no_runs() {
+    let statistics = Statistics::new(&[2.5]);
+    assert_eq!(
+        (statistics.mean, statistics.median, statistics.std),
+        (2.5, 2.5, 0.0)
+    );
+
+    let statistics = Statistics::new(&[]);
+    assert!(statistics.mean.is_nan() && statistics.median.is_nan() && statistics.std.is_nan());
+}
+
+#[test]
+fn summaries_leave_out_failed_runs() {
+    let runs = vec![
+        run("v7", 1, 10.0),
+        run("v6", 1, 8.0),
+        failed_run("v7", 2),
+        run("v7", 3, 20.0),
+    ];
+    let summaries = summarize(&runs);
+    assert_eq!(
+        summaries
+            .iter()
+            .map(|s| s.solver.as_str())
+            .collect::<Vec<_>>(),
+        ["v7", "v6"]
+    );
+    let v7 = &summaries[0];
+    assert_eq!(v7.no_of_runs, 2);
+    assert_eq!(v7.failed_runs.len(), 1);
+    assert_eq!(v7.failed_runs[0].1, 2);
+    assert!((v7.objective.mean - 15.0).abs() < EPSILON);
+    assert!((v7.iterations.median - 20.0).abs() < EPSILON);
+    assert_eq!(summaries[1].no_of_runs, 1);
+    assert!(summaries[1].failed_runs.is_empty());
+}
+
+#[test]
+fn markdown_report_lists_failed_runs() {
+    let summaries = summarize(&[run("v7", 1, 10.0), failed_run("v7", 2)]);
+    let report = markdown_report(&summaries);
+    let lines = report.lines().collect::<Vec<_>>();
+    assert!(lines[0].starts_with("| solver | time limit ms | runs | failed | objective mean |"));
+    assert_eq!(lines[0].matches('|').count(), lines[1].matches('|').count());
+    assert!(lines[2].starts_with("| v7 | 100 | 1 | 1 | 10.000 | 10.000 | 0.000 | 10.0 |"));
+    assert!(report.contains(
+        "Failed runs:\n\n- v7 with seed 2 and time limit 100ms on p_0.json: \
+         Invalid problem: Person 3 is defined more than once\n"
+    ));
+
+    let report = markdown_report(&summarize(&[run("v7", 1, 10.0)]));
+    assert!(!report.contains("Failed runs"));
+}
+
+#[test]
+fn csv_report_has_a_row_per_summary() {
+    let summaries = summarize(&[run("v7", 1, 10.0), run("v6", 2, 5.0), failed_run("v6", 3)]);
+    let report = csv_report(&summaries);
+    let rows = report.lines().collect::<Vec<_>>();
+    assert_eq!(rows.len(), 3);
+    assert!(rows[0].starts_with("solver,time_limit_ms,runs,failed,objective_mean,"));
+    assert_eq!(rows[1], "v7,100,1,0,10,10,0,10,10,0,100,100,0");
+    assert_eq!(rows[2], "v6,100,1,1,5,5,0,20,20,0,100,100,0");
+}
+
+#[test]
+fn invalid_problems_fail_their_runs_only() {
+    let valid = generate_problem_with_rng(20, 8, &mut StdRng::seed_from_u64(0));
+    let mut invalid = generate_problem_with_rng(20, 8, &mut StdRng::seed_from_u64(0));
+    invalid.people[1].id = invalid.people[0].id;
+    let problems = vec![
+        ExperimentProblem {
+            name: "valid.json".to_string(),
+            problem: valid,
+            groups: None,
+        },
+        ExperimentProblem {
+            name: "invalid.json".to_string(),
+            problem: invalid,
+            groups: None,
+        },
+    ];
+    let runs = run_experiment(
+        &problems,
+        &["v6".to_string(), "v7".to_string()],
+        &[1, 2],
+        &[0],
+    );
+    assert_eq!(runs.len(), 8);
+    for run in runs.iter() {
+        match run.problem.as_str() {
+            "valid.json" => assert!(run.result.is_ok(), "{:?}", run),
+            _ => assert_eq!(
+                run.result.as_ref().unwrap_err(),
+                "Invalid problem: Person 0 is defined more than once"
+            ),
+        }
+    }
+    let summaries = summarize(&runs);
+    assert!(summaries
+        .iter()
+        .all(|s| s.no_of_runs == 2 && s.failed_runs.len() == 2));
+}
+
+#[test]
+fn reports_have_group_columns_only_with_planted_groups() {
+    let with_groups = |solver: &str, seed: u64, share: f64| {
+        let mut run = run(solver, seed, 10.0);
+        if let Ok(result) = run.result.as_mut() {
+            result.same_group_pair_share = Some(share);
+        }
+        run
+    };
+    let summaries = summarize(&[
+        with_groups("v7", 1, 0.5),
+        with_groups("v7", 2, 0.7),
+        run("v7", 3, 10.0),
+        run("v6", 1, 10.0),
+    ]);
+    let v7 = summaries[0].same_group_pair_share.unwrap();
+    assert!((v7.mean - 0.6).abs() < EPSILON);
+    assert!(summaries[1].same_group_pair_share.is_none());
+
+    let markdown = markdown_report(&summaries);
+    let lines = markdown.lines().collect::<Vec<_>>();
+    assert!(lines[0]
+        .ends_with("| same group pairs mean | same group pairs median | same group pairs std |"));
+    assert!(lines[1..]
+        .iter()
+        .all(|line| line.matches('|').count() == lines[0].matches('|').count()));
+    assert!(lines[2].ends_with("| 0.600 | 0.600 | 0.100 |"));
+    let csv = csv_report(&summaries);
+    let rows = csv.lines().collect::<Vec<_>>();
+    assert!(
+        rows[0].ends_with(",same_group_pairs_mean,same_group_pairs_median,same_group_pairs_std")
+    );
+    assert!(rows[2].ends_with(",,,"));
+
+    let summaries = summarize(&[run("v7", 1, 10.0)]);
+    assert!(!markdown_report(&summaries).contains("same group"));
+    assert!(!csv_report(&summaries).contains("same_group"));
+}
+
+#[test]
+fn runs_on_problems_with_groups_measure_them() {
+    let profile = GeneratorProfile::named("communities").unwrap();
+    let (problem, groups) =
+        generate_problem_with_groups(&profile, 30, 10, &mut StdRng::seed_from_u64(0));
+    let problems = vec![ExperimentProblem {
+        name: "p_0.json".to_string(),
+        problem,
+        groups,
+    }];
+    let runs = run_experiment(&problems, &["v7".to_string()], &[1], &[10]);
+    let share = runs[0]
+        .result
+        .as_ref()
+        .unwrap()
+        .same_group_pair_share
+        .unwrap();
+    assert!((0.0..=1.0).contains(&share));
+}
+
+#[test]
+fn runs_with_only_single_person_tables_are_left_out_of_group_statistics() {
+    let profile = GeneratorProfile::named("communities").unwrap();
+    let (problem, groups) =
+        generate_problem_with_groups(&profile, 30, 10, &mut StdRng::seed_from_u64(0));
+    // Three people who can only come on three different dates with one table each
+    let mut alone = generate_problem_with_rng(3, 3, &mut StdRng::seed_from_u64(0));
+    let first_date = alone.tables[0].date;
+    for (index, (table, person)) in alone
+        .tables
+        .iter_mut()
+        .zip(alone.people.iter_mut())
+        .enumerate()
+    {
+        table.date = first_date + chrono::Duration::days(index as i64);
+        person.available_dates = Some([table.date.date_naive()].into());
+    }
+    let problems = vec![
+        ExperimentProblem {
+            name: "p_0.json".to_string(),
+            problem,
+            groups,
+        },
+        ExperimentProblem {
+            name: "alone.json".to_string(),
+            problem: alone,
+            groups: Some(vec![vec![0, 1, 2]]),
+        },
+    ];
+    let runs = run_experiment(&problems, &["v7".to_string()], &[1], &[10]);
+    let shares = runs
+        .iter()
+        .map(|run| run.result.as_ref().unwrap().same_group_pair_share)
+        .collect::<Vec<_>>();
+    assert!(shares[0].is_some());
+    assert_eq!(shares[1], None);
+    let statistics = summarize(&runs)[0].same_group_pair_share.unwrap();
+    assert_eq!(statistics.mean, shares[0].unwrap());
+}
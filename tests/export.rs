@@ -0,0 +1,147 @@
+//! Seating plans exported for event staff
+
+use std::collections::BTreeMap;
+
+use chrono::{TimeZone, Utc};
+
+use table_problem::export::{csv_export, html_export, markdown_export};
+use table_problem::problem::{
+    Person, ProblemDescription, RelationSemantics, Solution, TableDay, TableLayout,
+};
+
+/// Two dates with the later date given first, one table left empty and one person unassigned
+fn problem_and_solution() -> (ProblemDescription, Solution) {
+    let table = |id, table_id, day, layout| TableDay {
+        id,
+        table_id,
+        date: Utc.with_ymd_and_hms(2024, 3, day, 0, 0, 0).unwrap(),
+        layout,
+        name: None,
+        tags: vec![],
+        metadata: BTreeMap::new(),
+    };
+    let problem = ProblemDescription {
+        people: (0..6)
+            .map(|id| Person {
+                id,
+                visits: vec![],
+                available_dates: None,
+                unassigned_penalty: Some(1.0),
+                required_seatings: 1,
+                name: None,
+                email: None,
+                tags: vec![],
+                metadata: BTreeMap::new(),
+            })
+            .collect(),
+        tables: vec![
+            table(0, 2, 5, TableLayout::Ring),
+            table(1, 1, 4, TableLayout::Bench),
+            table(2, 0, 4, TableLayout::Ring),
+            table(3, 0, 5, TableLayout::Ring),
+        ],
+        people_relations: BTreeMap::new(),
+        relation_semantics: RelationSemantics::Symmetric,
+    };
+    let solution = Solution {
+        solution_per_table: [(0, vec![4]), (1, vec![3, 1, 2]), (2, vec![0]), (3, vec![])]
+            .into_iter()
+            .collect(),
+        unassigned: vec![5],
+    };
+    (problem, solution)
+}
+
+/// Same plan with names for some of the people and tables
+fn named_problem_and_solution() -> (ProblemDescription, Solution) {
+    let (mut problem, solution) = problem_and_solution();
+    problem.people[1].name = Some("Ada, \"Countess\" Lovelace".to_string());
+    problem.people[1].email = Some("ada@example.com".to_string());
+    problem.people[1].tags = vec!["speaker".to_string(), "vegetarian".to_string()];
+    problem.people[5].name = Some("<Alan>".to_string());
+    problem.tables[1].name = Some("Long table".to_string());
+    (problem, solution)
+}
+
+#[test]
+fn csv_lists_seats_by_date_and_table() {
+    let (problem, solution) = problem_and_solution();
+    assert_eq!(
+        csv_export(&problem, &solution),
+        "date,table_id,table_day_id,table,layout,seat,person_id,person,email,tags\n\
+         2024-03-04,0,2,Table 0,ring,1,0,Person 0,,\n\
+         2024-03-04,1,1,Table 1,bench,1,3,Person 3,,\n\
+         2024-03-04,1,1,Table 1,bench,2,1,Person 1,,\n\
+         2024-03-04,1,1,Table 1,bench,3,2,Person 2,,\n\
+         2024-03-05,2,0,Table 2,ring,1,4,Person 4,,\n\
+         ,,,,,,5,Person 5,,\n"
+    );
+}
+
+#[test]
+fn csv_carries_names_emails_and_tags() {
+    let (problem, solution) = named_problem_and_solution();
+    let csv = csv_export(&problem, &solution);
+    assert!(csv.contains(
+        "2024-03-04,1,1,Long table,bench,2,1,\"Ada, \"\"Countess\"\" Lovelace\",\
+         ada@example.com,speaker;vegetarian\n"
+    ));
+    assert!(csv.ends_with(",,,,,,5,<Alan>,,\n"));
+}
+
+#[test]
+fn markdown_lists_seats_by_date_and_table() {
+    let (problem, solution) = problem_and_solution();
+    assert_eq!(
+        markdown_export(&problem, &solution),
+        "# Seating plan\n\
+         \n## 2024-03-04\n\
+         \n### Table 0 (ring)\n\n1. Person 0\n\
+         \n### Table 1 (bench)\n\n1. Person 3\n2. Person 1\n3. Person 2\n\
+         \n## 2024-03-05\n\
+         \n### Table 2 (ring)\n\n1. Person 4\n\
+         \n## Without a seat\n\n- Person 5\n"
+    );
+}
+
+#[test]
+fn html_draws_every_seated_table() {
+    let (problem, solution) = problem_and_solution();
+    let html = html_export(&problem, &solution);
+    assert_eq!(html.matches("<svg").count(), 3);
+    assert_eq!(html.matches("<circle").count(), 2);
+    assert_eq!(html.matches("<rect").count(), 1);
+    let position = |text: &str| html.find(text).unwrap();
+    assert!(position("2024-03-04") < position("2024-03-05"));
+    // Seat order around the bench
+    assert!(position("Person 3") < position("Person 1"));
+    assert!(position("Person 1") < position("Person 2"));
+    assert!(position("Without a seat") < position("Person 5"));
+}
+
+#[test]
+fn names_replace_ids_in_markdown_and_html() {
+    let (problem, solution) = named_problem_and_solution();
+    let markdown = markdown_export(&problem, &solution);
+    assert!(
+        markdown.contains("### Long table (bench)\n\n1. Person 3\n2. Ada, \"Countess\" Lovelace\n")
+    );
+    assert!(markdown.contains("- <Alan>\n"));
+
+    let html = html_export(&problem, &solution);
+    assert!(html.contains("<figcaption>Long table</figcaption>"));
+    assert!(html.contains(">Ada, &quot;Countess&quot; Lovelace</text>"));
+    assert!(html.contains("<li>&lt;Alan&gt;</li>"));
+}
+
+#[test]
+fn markdown_escapes_pipes_and_newlines_in_names() {
+    let (mut problem, solution) = problem_and_solution();
+    problem.people[1].name = Some("Ada | Lovelace\nCountess".to_string());
+    problem.people[5].name = Some("Alan\r\nTuring".to_string());
+    problem.tables[1].name = Some("Long | table\n".to_string());
+    let markdown = markdown_export(&problem, &solution);
+    assert!(markdown
+        .contains("### Long \\| table  (bench)\n\n1. Person 3\n2. Ada \\| Lovelace Countess\n"));
+    assert!(markdown.ends_with("- Alan Turing\n"));
+}
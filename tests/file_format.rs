@@ -0,0 +1,206 @@
+//! Problem files written by earlier versions and the fields solvers ignore
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use table_problem::binary::{
+    problem_from_msgpack, problem_to_msgpack, solution_from_msgpack, solution_to_msgpack,
+    BinaryError,
+};
+use table_problem::explain::explain;
+use table_problem::generator::generate_problem_with_rng;
+use table_problem::problem::{ProblemDescription, Solution};
+use table_problem::schema::{
+    migrate, problem_from_json, problem_json_schema, problem_to_json, SchemaError, SCHEMA_VERSION,
+};
+
+const PROBLEM: &str = r#"{
+    "people": [
+        {"id": 0, "visits": [{"table_id": 1, "at": 1709251200}], "name": "Ada",
+         "email": "ada@example.com", "tags": ["speaker"], "metadata": {"department": "Research"}},
+        {"id": 1, "visits": []}
+    ],
+    "tables": [
+        {"id": 0, "table_id": 1, "date": 1709510400, "name": "Oak", "tags": ["window"],
+         "metadata": {"room": "Main hall"}}
+    ],
+    "people_relations": {"0": {"1": 1.5}}
+}"#;
+
+#[test]
+fn names_and_metadata_round_trip() {
+    let problem: ProblemDescription = serde_json::from_str(PROBLEM).unwrap();
+    assert_eq!(problem.people[0].name.as_deref(), Some("Ada"));
+    assert_eq!(problem.people[0].metadata["department"], "Research");
+    assert_eq!(problem.tables[0].tags, vec!["window"]);
+
+    let written = serde_json::to_string(&problem).unwrap();
+    let read_again: ProblemDescription = serde_json::from_str(&written).unwrap();
+    assert_eq!(
+        read_again.people[0].email.as_deref(),
+        Some("ada@example.com")
+    );
+    assert_eq!(read_again.people[0].tags, vec!["speaker"]);
+    assert_eq!(read_again.tables[0].metadata["room"], "Main hall");
+    // Nothing is written for people without names or metadata
+    assert!(written.contains(r#"{"id":1,"visits":[],"required_seatings":1}"#));
+    assert_eq!(read_again.people[1].name, None);
+    assert!(read_again.people[1].metadata.is_empty());
+}
+
+#[test]
+fn explanation_shows_names() {
+    let problem: ProblemDescription = serde_json::from_str(PROBLEM).unwrap();
+    let solution = Solution {
+        solution_per_table: [(0, vec![0, 1])].into_iter().collect(),
+        unassigned: vec![],
+    };
+    let explanation = explain(&problem, &solution).to_string();
+    assert!(explanation.contains("Table day 0 (table 1 Oak on 2024-03-04"));
+    assert!(explanation.contains("  person 0 (Ada): recency"));
+    assert!(explanation.contains("  neighbours 0 (Ada) and 1: 1.500"));
+}
+
+#[test]
+fn unversioned_files_are_upgraded() {
+    let problem = problem_from_json(PROBLEM).unwrap();
+    assert_eq!(problem.people.len(), 2);
+    assert_eq!(problem.people_relations[&0][&1], 1.5);
+
+    let upgraded = migrate(serde_json::from_str(PROBLEM).unwrap()).unwrap();
+    assert_eq!(upgraded["schema_version"], SCHEMA_VERSION);
+}
+
+#[test]
+fn written_files_carry_the_version_first() {
+    let problem = problem_from_json(PROBLEM).unwrap();
+    let written = problem_to_json(&problem).unwrap();
+    assert!(written.starts_with(&format!("{{\"schema_version\":{},", SCHEMA_VERSION)));
+
+    let read_again = problem_from_json(&written).unwrap();
+    assert_eq!(read_again.people[0].name.as_deref(), Some("Ada"));
+    assert_eq!(read_again.tables.len(), 1);
+    // Readers that do not know about versions still read the file
+    let unaware: ProblemDescription = serde_json::from_str(&written).unwrap();
+    assert_eq!(unaware.people.len(), 2);
+}
+
+#[test]
+fn unknown_versions_are_rejected() {
+    let with_version =
+        |version: &str| PROBLEM.replacen('{', &format!("{{\"schema_version\": {},", version), 1);
+    assert!(problem_from_json(&with_version("1")).is_ok());
+    assert!(matches!(
+        problem_from_json(&with_version(&(SCHEMA_VERSION + 1).to_string())),
+        Err(SchemaError::UnsupportedVersion(_))
+    ));
+    for version in ["0", "-1", "1.5", "\"2\"", "null"] {
+        assert!(
+            matches!(
+                problem_from_json(&with_version(version)),
+                Err(SchemaError::InvalidVersion(_))
+            ),
+            "{}",
+            version
+        );
+    }
+    assert!(matches!(
+        problem_from_json("[1, 2]"),
+        Err(SchemaError::NotAnObject) | Err(SchemaError::Json(_))
+    ));
+}
+
+#[test]
+fn json_schema_describes_written_files() {
+    let schema: serde_json::Value = serde_json::from_str(&problem_json_schema()).unwrap();
+    let required = schema["required"].as_array().unwrap();
+    for field in ["schema_version", "people", "tables", "people_relations"] {
+        assert!(
+            required.contains(&field.into()),
+            "{} is not required",
+            field
+        );
+    }
+
+    let written: serde_json::Value =
+        serde_json::from_str(&problem_to_json(&problem_from_json(PROBLEM).unwrap()).unwrap())
+            .unwrap();
+    let properties = schema["properties"].as_object().unwrap();
+    for field in written.as_object().unwrap().keys() {
+        assert!(
+            properties.contains_key(field),
+            "{} is not in the schema",
+            field
+        );
+    }
+    let person = &schema["definitions"]["Person"]["properties"];
+    for field in ["id", "visits", "name", "email", "tags", "metadata"] {
+        assert!(
+            person.get(field).is_some(),
+            "Person.{} is not in the schema",
+            field
+        );
+    }
+    assert_eq!(
+        schema["definitions"]["PersonVisit"]["properties"]["at"]["type"],
+        "integer"
+    );
+}
+
+#[test]
+fn binary_files_round_trip() {
+    let problem = generate_problem_with_rng(200, 60, &mut StdRng::seed_from_u64(0));
+    let binary = problem_to_msgpack(&problem).unwrap();
+    let json = problem_to_json(&problem).unwrap();
+    assert!(binary.len() < json.len());
+    // Floats are written exactly, so the problems are the same
+    let read_again = problem_from_msgpack(&binary).unwrap();
+    assert_eq!(problem_to_json(&read_again).unwrap(), json);
+
+    let with_names = problem_from_json(PROBLEM).unwrap();
+    let read_again = problem_from_msgpack(&problem_to_msgpack(&with_names).unwrap()).unwrap();
+    assert_eq!(read_again.people[0].name.as_deref(), Some("Ada"));
+    assert_eq!(read_again.tables[0].metadata["room"], "Main hall");
+    assert!(read_again.people[1].tags.is_empty());
+
+    let solution: Solution =
+        serde_json::from_str(r#"{"solution_per_table": {"0": [0, 1]}, "unassigned": []}"#).unwrap();
+    let read_again = solution_from_msgpack(&solution_to_msgpack(&solution).unwrap()).unwrap();
+    assert_eq!(read_again.solution_per_table[&0], vec![0, 1]);
+}
+
+#[test]
+fn binary_files_of_older_versions_are_upgraded() {
+    let problem = problem_from_json(PROBLEM).unwrap();
+    let json = problem_to_json(&problem).unwrap();
+    let mut fields = serde_json::to_value(&problem).unwrap();
+    // Without a version field and with version 1, relation keys as integers and as strings
+    let unversioned = rmp_serde::to_vec_named(&problem).unwrap();
+    fields["schema_version"] = 1.into();
+    for binary in [unversioned, rmp_serde::to_vec_named(&fields).unwrap()] {
+        let read_again = problem_from_msgpack(&binary).unwrap();
+        assert_eq!(problem_to_json(&read_again).unwrap(), json);
+    }
+}
+
+#[test]
+fn binary_files_of_newer_versions_are_rejected() {
+    let mut fields = serde_json::to_value(problem_from_json(PROBLEM).unwrap()).unwrap();
+    fields["schema_version"] = (SCHEMA_VERSION + 1).into();
+    let binary = rmp_serde::to_vec_named(&fields).unwrap();
+    assert!(matches!(
+        problem_from_msgpack(&binary),
+        Err(BinaryError::Schema(SchemaError::UnsupportedVersion(v))) if v == SCHEMA_VERSION + 1
+    ));
+    // A future version that changed the layout
+    let binary =
+        rmp_serde::to_vec_named(&serde_json::json!({"schema_version": 3, "problems": []})).unwrap();
+    assert!(matches!(
+        problem_from_msgpack(&binary),
+        Err(BinaryError::Schema(SchemaError::UnsupportedVersion(3)))
+    ));
+    assert!(matches!(
+        problem_from_msgpack(PROBLEM.as_bytes()),
+        Err(BinaryError::Decode(_))
+    ));
+}
@@ -0,0 +1,246 @@
+//! Generated problems must be valid for every scenario profile
+
+use std::path::Path;
+
+use itertools::Itertools;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use table_problem::algorithm::v7;
+use table_problem::generator::manifest::{
+    generate_files, GenerateSettings, Manifest, MANIFEST_FILE,
+};
+use table_problem::generator::metrics::same_group_pair_share;
+use table_problem::generator::profile::{GeneratorProfile, RelationProfile, PROFILE_NAMES};
+use table_problem::generator::{generate_problem_with_groups, generate_problem_with_profile};
+use table_problem::problem::{PersonId, ProblemDescription, Solution};
+use table_problem::validator::{validate_problem, validate_solution};
+
+#[test]
+fn named_profiles_generate_solvable_problems() {
+    for name in PROFILE_NAMES {
+        let profile = GeneratorProfile::named(name).unwrap();
+        for (seed, no_of_people) in [(0, 1), (1, 7), (2, 60), (3, 150)] {
+            let problem = generate_problem_with_profile(
+                &profile,
+                no_of_people,
+                profile.no_of_table_days(no_of_people),
+                &mut StdRng::seed_from_u64(seed),
+            );
+            validate_problem(&problem).unwrap_or_else(|e| panic!("{}: {}", name, e));
+            let results = v7::solve_with_rng(
+                &problem,
+                chrono::Duration::zero(),
+                &mut StdRng::seed_from_u64(seed),
+            );
+            validate_solution(&problem, &results.solution);
+        }
+    }
+}
+
+#[test]
+fn tight_capacity_has_one_seat_per_person() {
+    let profile = GeneratorProfile::named("tight_capacity").unwrap();
+    assert_eq!(profile.no_of_table_days(60), 10);
+    assert_eq!(profile.no_of_table_days(61), 11);
+}
+
+#[test]
+fn profiles_with_few_days_get_every_table_day() {
+    let profile = GeneratorProfile::load(Path::new("examples/profiles/conference.toml")).unwrap();
+    for no_of_people in [20, 100, 300] {
+        let no_of_table_days = profile.no_of_table_days(no_of_people);
+        let problem = generate_problem_with_profile(
+            &profile,
+            no_of_people,
+            no_of_table_days,
+            &mut StdRng::seed_from_u64(0),
+        );
+        assert_eq!(problem.tables.len(), no_of_table_days);
+        validate_problem(&problem).unwrap();
+    }
+}
+
+#[test]
+fn example_spec_overrides_only_given_fields() {
+    let profile = GeneratorProfile::load(Path::new("examples/profiles/conference.toml")).unwrap();
+    assert_eq!(profile.no_of_days, 2);
+    assert_eq!(profile.max_visits, GeneratorProfile::default().max_visits);
+    assert!(matches!(
+        profile.relations,
+        RelationProfile::Teams { team_size: 12, .. }
+    ));
+}
+
+#[test]
+fn json_spec_is_read() {
+    let path = std::env::temp_dir().join("generator_profile_test.json");
+    std::fs::write(
+        &path,
+        r#"{"min_visits": 2, "relations": {"model": "uniform", "density": 0.1, "min_score": -1.0, "max_score": 1.0}}"#,
+    )
+    .unwrap();
+    let profile = GeneratorProfile::load(&path).unwrap();
+    assert_eq!(profile.min_visits, 2);
+    assert_eq!(
+        profile.relations,
+        RelationProfile::Uniform {
+            density: 0.1,
+            min_score: -1.0,
+            max_score: 1.0
+        }
+    );
+}
+
+#[test]
+fn invalid_specs_are_rejected() {
+    let path = std::env::temp_dir().join("generator_profile_invalid.toml");
+    for content in [
+        "unknown_field = 1",
+        "new_joiner_share = 1.5",
+        "min_visits = 4\nmax_visits = 2",
+        "no_of_days = 0",
+    ] {
+        std::fs::write(&path, content).unwrap();
+        assert!(GeneratorProfile::load(&path).is_err(), "{}", content);
+    }
+}
+
+#[test]
+fn non_finite_values_are_rejected() {
+    let path = std::env::temp_dir().join("generator_profile_non_finite.toml");
+    for content in [
+        "capacity_slack = nan",
+        "capacity_slack = inf",
+        "fully_available_share = nan",
+        "[relations]\nmodel = \"uniform\"\ndensity = 0.1\nmin_score = nan\nmax_score = 1.0",
+        "[relations]\nmodel = \"uniform\"\ndensity = 0.1\nmin_score = -1.0\nmax_score = inf",
+    ] {
+        std::fs::write(&path, content).unwrap();
+        let error = GeneratorProfile::load(&path).unwrap_err();
+        assert!(
+            error.to_string().contains("finite"),
+            "{}: {}",
+            content,
+            error
+        );
+    }
+}
+
+#[test]
+fn communities_are_planted_with_conflicts_between_groups() {
+    let profile = GeneratorProfile::named("communities").unwrap();
+    let (problem, groups) =
+        generate_problem_with_groups(&profile, 100, 40, &mut StdRng::seed_from_u64(4));
+    let groups = groups.unwrap();
+    let mut people = groups.iter().flatten().cloned().collect_vec();
+    people.sort();
+    assert_eq!(people, (0..100).collect_vec());
+    assert!(groups[..groups.len() - 1]
+        .iter()
+        .all(|group| (3..=10).contains(&group.len())));
+
+    let same_group = |p1: &PersonId, p2: &PersonId| {
+        groups
+            .iter()
+            .any(|group| group.contains(p1) && group.contains(p2))
+    };
+    for (p1, relations) in problem.people_relations.iter() {
+        for (p2, score) in relations {
+            if same_group(p1, p2) {
+                assert!((0.5..=2.0).contains(score));
+            } else {
+                assert!((-2.0..=-0.5).contains(score));
+            }
+        }
+    }
+}
+
+#[test]
+fn uniform_relations_have_no_groups() {
+    let (_, groups) = generate_problem_with_groups(
+        &GeneratorProfile::default(),
+        30,
+        10,
+        &mut StdRng::seed_from_u64(5),
+    );
+    assert_eq!(groups, None);
+}
+
+#[test]
+fn seating_groups_together_recovers_them_fully() {
+    let groups = vec![vec![0, 1, 2], vec![3, 4], vec![5]];
+    let solution = |tables: Vec<Vec<PersonId>>| Solution {
+        solution_per_table: tables.into_iter().enumerate().collect(),
+        unassigned: vec![],
+    };
+    assert_eq!(
+        same_group_pair_share(&groups, &solution(groups.clone())),
+        Some(1.0)
+    );
+    // Only 0-1 of the pairs 0-1, 0-3, 1-3, 2-4, 2-5 and 4-5 is in the same group
+    assert_eq!(
+        same_group_pair_share(&groups, &solution(vec![vec![0, 1, 3], vec![2, 4, 5]])),
+        Some(1.0 / 6.0)
+    );
+    assert_eq!(
+        same_group_pair_share(&groups, &solution(vec![vec![0], vec![1]])),
+        None
+    );
+}
+
+fn generate_settings(seed: u64, planted: bool) -> GenerateSettings {
+    GenerateSettings {
+        no_of_problems: 3,
+        min_people: 10,
+        max_people: 30,
+        no_of_table_days: None,
+        seed,
+        groups: true,
+        planted,
+        profile: GeneratorProfile::named("communities").unwrap(),
+    }
+}
+
+#[test]
+fn manifest_lists_generated_files() {
+    let output_dir = std::env::temp_dir().join("generator_manifest_test");
+    let _ = std::fs::remove_dir_all(&output_dir);
+    let manifest = generate_files(&generate_settings(7, false), &output_dir).unwrap();
+    let written: Manifest =
+        serde_json::from_str(&std::fs::read_to_string(output_dir.join(MANIFEST_FILE)).unwrap())
+            .unwrap();
+    assert_eq!(written.problems.len(), 3);
+    for entry in written.problems.iter() {
+        let problem: ProblemDescription =
+            serde_json::from_str(&std::fs::read_to_string(output_dir.join(&entry.file)).unwrap())
+                .unwrap();
+        assert_eq!(problem.people.len(), entry.no_of_people);
+        assert_eq!(problem.tables.len(), entry.no_of_table_days);
+        assert!((10..30).contains(&entry.no_of_people));
+        assert!(output_dir
+            .join(entry.groups_file.as_ref().unwrap())
+            .exists());
+    }
+
+    // Same seed, same problems
+    let again = generate_files(&generate_settings(7, false), &output_dir).unwrap();
+    assert_eq!(
+        serde_json::to_string(&manifest).unwrap(),
+        serde_json::to_string(&again).unwrap()
+    );
+}
+
+#[test]
+fn manifest_records_planted_optimum() {
+    let output_dir = std::env::temp_dir().join("generator_manifest_planted_test");
+    let _ = std::fs::remove_dir_all(&output_dir);
+    let manifest = generate_files(&generate_settings(8, true), &output_dir).unwrap();
+    for entry in manifest.problems.iter() {
+        assert!(entry.optimal_value.is_some());
+        assert!(output_dir
+            .join(entry.optimum_file.as_ref().unwrap())
+            .exists());
+        assert_eq!(entry.groups_file, None);
+    }
+}
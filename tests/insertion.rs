@@ -0,0 +1,138 @@
+//! Small problems where the best insertion is known. Without time for the search the solvers
+//! return what greedy insertion built, which has to be optimal here.
+
+use std::collections::BTreeMap;
+
+use chrono::{Duration, TimeZone, Utc};
+use itertools::Itertools;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use table_problem::experiment::{solver_by_name, SOLVER_NAMES};
+use table_problem::objective_value_calculator::v1::ObjectiveValueCalculator;
+use table_problem::problem::{
+    Person, PersonId, PersonVisit, ProblemDescription, RelationSemantics, Solution, TableDay,
+    TableLayout,
+};
+use table_problem::validator::validate_solution;
+
+fn person(id: PersonId, visits: Vec<PersonVisit>) -> Person {
+    Person {
+        id,
+        visits,
+        available_dates: None,
+        unassigned_penalty: None,
+        required_seatings: 1,
+        name: None,
+        email: None,
+        tags: vec![],
+        metadata: BTreeMap::new(),
+    }
+}
+
+fn problem(
+    people: Vec<Person>,
+    no_of_tables: usize,
+    relations: &[(PersonId, PersonId, f64)],
+) -> ProblemDescription {
+    let date = Utc.with_ymd_and_hms(2024, 3, 4, 0, 0, 0).unwrap();
+    let mut people_relations: BTreeMap<PersonId, BTreeMap<PersonId, f64>> = BTreeMap::new();
+    for (person_1_id, person_2_id, value) in relations {
+        people_relations
+            .entry(*person_1_id)
+            .or_default()
+            .insert(*person_2_id, *value);
+    }
+    ProblemDescription {
+        people,
+        tables: (0..no_of_tables)
+            .map(|id| TableDay {
+                id,
+                table_id: id,
+                date,
+                layout: TableLayout::Ring,
+                name: None,
+                tags: vec![],
+                metadata: BTreeMap::new(),
+            })
+            .collect(),
+        people_relations,
+        relation_semantics: RelationSemantics::Symmetric,
+    }
+}
+
+/// Every solver with no time for the search must find the best of the candidate solutions
+fn assert_greedy_insertion_finds_best(problem: &ProblemDescription, candidates: &[Solution]) {
+    let calculator = ObjectiveValueCalculator::new(problem);
+    let best_value = candidates
+        .iter()
+        .map(|solution| calculator.solution_value(solution))
+        .fold(f64::MIN, f64::max);
+    for solver_name in SOLVER_NAMES {
+        let solve = solver_by_name(solver_name).unwrap();
+        let results = solve(problem, Duration::zero(), &mut StdRng::seed_from_u64(0));
+        validate_solution(problem, &results.solution);
+        let value = calculator.solution_value(&results.solution);
+        assert!(
+            (value - best_value).abs() < 1e-9,
+            "{} found {:?} worth {} instead of {}",
+            solver_name,
+            results.solution,
+            value,
+            best_value
+        );
+    }
+}
+
+#[test]
+fn person_goes_to_the_best_table_not_the_last_one() {
+    // The person already sat at the second table, so the first one is worth more
+    let visit = PersonVisit {
+        table_id: 1,
+        at: Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap(),
+    };
+    let problem = problem(vec![person(0, vec![visit])], 2, &[]);
+    let candidates = (0..2)
+        .map(|table_day_id| Solution {
+            solution_per_table: (0..2)
+                .map(|id| (id, if id == table_day_id { vec![0] } else { vec![] }))
+                .collect(),
+            unassigned: vec![],
+        })
+        .collect_vec();
+    assert_greedy_insertion_finds_best(&problem, &candidates);
+}
+
+#[test]
+fn person_goes_to_the_best_position_at_the_table() {
+    // Last person only likes 1 and 2, who are only next to each other when inserted
+    // at a position other than the first
+    let problem = problem(
+        (0..4).map(|id| person(id, vec![])).collect(),
+        1,
+        &[(1, 3, 1.0), (2, 3, 1.0)],
+    );
+    let candidates = (0..4)
+        .permutations(4)
+        .map(|people| Solution {
+            solution_per_table: [(0, people)].into_iter().collect(),
+            unassigned: vec![],
+        })
+        .collect_vec();
+    assert_greedy_insertion_finds_best(&problem, &candidates);
+}
+
+#[test]
+fn people_fill_empty_tables() {
+    // People who dislike each other have to be spread over the tables
+    let problem = problem(
+        (0..3).map(|id| person(id, vec![])).collect(),
+        3,
+        &[(0, 1, -1.0), (0, 2, -1.0), (1, 2, -1.0)],
+    );
+    let candidates = vec![Solution {
+        solution_per_table: (0..3).map(|id| (id, vec![id])).collect(),
+        unassigned: vec![],
+    }];
+    assert_greedy_insertion_finds_best(&problem, &candidates);
+}
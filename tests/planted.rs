@@ -0,0 +1,71 @@
+//! No solver may beat the optimum a planted problem was built around
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use table_problem::experiment::{solver_by_name, SOLVER_NAMES};
+use table_problem::generator::planted::{generate_planted_problem, PLANTED_RELATION_SCORE};
+use table_problem::generator::profile::{GeneratorProfile, PROFILE_NAMES};
+use table_problem::objective_value_calculator::v1::ObjectiveValueCalculator;
+use table_problem::validator::{validate_problem, validate_solution};
+
+#[test]
+fn planted_solution_reaches_the_upper_bound() {
+    for name in PROFILE_NAMES {
+        let profile = GeneratorProfile::named(name).unwrap();
+        for (seed, no_of_people) in [(0, 1), (1, 2), (2, 7), (3, 60), (4, 151)] {
+            let (problem, optimum) = generate_planted_problem(
+                &profile,
+                no_of_people,
+                profile.no_of_table_days(no_of_people),
+                &mut StdRng::seed_from_u64(seed),
+            );
+            validate_problem(&problem).unwrap();
+            validate_solution(&problem, &optimum.solution);
+            // 2 for every person on their own and twice the planted score from neighbours
+            let upper_bound = if no_of_people > 1 {
+                no_of_people as f64 * (2.0 + PLANTED_RELATION_SCORE)
+            } else {
+                2.0
+            };
+            assert!(
+                (optimum.optimal_value - upper_bound).abs() < 1e-9,
+                "{} with {} people: {} instead of {}",
+                name,
+                no_of_people,
+                optimum.optimal_value,
+                upper_bound
+            );
+        }
+    }
+}
+
+#[test]
+fn solvers_do_not_beat_the_planted_optimum() {
+    for seed in 0..3 {
+        let (problem, optimum) = generate_planted_problem(
+            &GeneratorProfile::default(),
+            40,
+            12,
+            &mut StdRng::seed_from_u64(seed),
+        );
+        let calculator = ObjectiveValueCalculator::new(&problem);
+        for solver_name in SOLVER_NAMES {
+            let solve = solver_by_name(solver_name).unwrap();
+            let results = solve(
+                &problem,
+                chrono::Duration::milliseconds(20),
+                &mut StdRng::seed_from_u64(seed),
+            );
+            validate_solution(&problem, &results.solution);
+            let value = calculator.solution_value(&results.solution);
+            assert!(
+                value <= optimum.optimal_value + 1e-9,
+                "{} found {} above the optimum {}",
+                solver_name,
+                value,
+                optimum.optimal_value
+            );
+        }
+    }
+}
@@ -0,0 +1,224 @@
+//! Every solver must return a valid solution whose objective matches a full recalculation
+
+use std::collections::BTreeMap;
+use std::iter;
+
+use chrono::Duration;
+use proptest::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use table_problem::experiment::{solver_by_name, SOLVER_NAMES};
+use table_problem::generator::generate_problem_with_rng;
+use table_problem::objective_value_calculator::v1::ObjectiveValueCalculator;
+use table_problem::problem::{
+    ProblemDescription, RelationSemantics, TableDay, TableLayout, MAX_PEOPLE_FOR_TABLE,
+};
+use table_problem::validator::{
+    check_solution, validate_problem, validate_solution, SolutionError,
+};
+
+fn assert_solvers_return_valid_solutions(problem: &ProblemDescription, seed: u64) {
+    let calculator = ObjectiveValueCalculator::new(problem);
+    for solver_name in SOLVER_NAMES {
+        let solve = solver_by_name(solver_name).unwrap();
+        let results = solve(
+            problem,
+            chrono::Duration::milliseconds(20),
+            &mut StdRng::seed_from_u64(seed),
+        );
+        validate_solution(problem, &results.solution);
+        let objective_value = calculator.solution_value(&results.solution);
+        assert!(
+            (results.objective_value - objective_value).abs() < 1e-6,
+            "{} reported objective {} but the solution is worth {}",
+            solver_name,
+            results.objective_value,
+            objective_value
+        );
+    }
+}
+
+/// Features the default generator leaves out, added to generated problems
+#[derive(Debug, Clone, Copy)]
+struct Variation {
+    /// Ring, all pairs, bench or a random layout per table
+    layouts: usize,
+    /// None, some or all people may be left without a seat
+    penalties: usize,
+    /// Some people must be seated twice, on extra tables
+    multiple_seatings: bool,
+    directed: bool,
+}
+
+fn variation_strategy() -> impl Strategy<Value = Variation> {
+    (0..4usize, 0..3usize, any::<bool>(), any::<bool>()).prop_map(
+        |(layouts, penalties, multiple_seatings, directed)| Variation {
+            layouts,
+            penalties,
+            multiple_seatings,
+            directed,
+        },
+    )
+}
+
+fn apply_variation(problem: &mut ProblemDescription, variation: Variation, rng: &mut impl Rng) {
+    if variation.multiple_seatings {
+        // Two new dates with a seat for each of the people seated twice, who can only come
+        // on those dates. They are the most constrained people, so solvers seat them first
+        // and the original tables are left for everybody else.
+        let dates = {
+            let last_date = problem.tables.iter().map(|t| t.date).max().unwrap();
+            [last_date + Duration::days(1), last_date + Duration::days(2)]
+        };
+        let mut no_of_people_seated_twice: usize = 0;
+        for person in problem.people.iter_mut().filter(|_| rng.gen_bool(0.3)) {
+            person.available_dates = Some(dates.iter().map(|d| d.date_naive()).collect());
+            person.required_seatings = 2;
+            no_of_people_seated_twice += 1;
+        }
+        let next_id = problem.tables.iter().map(|t| t.id).max().unwrap() + 1;
+        let next_table_id = problem.tables.iter().map(|t| t.table_id).max().unwrap() + 1;
+        let no_of_tables_per_date = no_of_people_seated_twice.div_ceil(MAX_PEOPLE_FOR_TABLE);
+        for (index, date) in dates
+            .iter()
+            .flat_map(|date| iter::repeat_n(*date, no_of_tables_per_date))
+            .enumerate()
+        {
+            problem.tables.push(TableDay {
+                id: next_id + index,
+                table_id: next_table_id + index % no_of_tables_per_date,
+                date,
+                layout: TableLayout::Ring,
+                name: None,
+                tags: vec![],
+                metadata: BTreeMap::new(),
+            });
+        }
+    }
+    for table in problem.tables.iter_mut() {
+        table.layout = match variation.layouts {
+            0 => TableLayout::Ring,
+            1 => TableLayout::AllPairs,
+            2 => TableLayout::Bench,
+            _ => {
+                [TableLayout::Ring, TableLayout::AllPairs, TableLayout::Bench][rng.gen_range(0..3)]
+            }
+        };
+    }
+    for person in problem.people.iter_mut() {
+        if variation.penalties == 2 || (variation.penalties == 1 && rng.gen_bool(0.3)) {
+            person.unassigned_penalty = Some(rng.gen_range(0.0..3.0));
+        }
+    }
+    if variation.directed {
+        problem.relation_semantics = RelationSemantics::Directed;
+        // Some pairs like each other in one direction and not in the other
+        let reversed = problem
+            .people_relations
+            .iter()
+            .flat_map(|(p1, r)| r.iter().map(move |(p2, value)| (*p2, *p1, *value)))
+            .step_by(3)
+            .collect::<Vec<_>>();
+        for (p1, p2, value) in reversed {
+            problem
+                .people_relations
+                .entry(p1)
+                .or_default()
+                .insert(p2, -value);
+        }
+    }
+}
+
+/// Problem with at least as many seats as people, generated with partial availability and
+/// varied along the features the generator leaves out
+fn problem_strategy() -> impl Strategy<Value = ProblemDescription> {
+    (
+        (
+            1..80usize,
+            0..4usize,
+            any::<u64>(),
+            any::<bool>(),
+            any::<bool>(),
+        ),
+        variation_strategy(),
+    )
+        .prop_map(
+            |(
+                (no_of_people, extra_table_days, seed, everybody_available, without_relations),
+                variation,
+            )| {
+                let no_of_table_days =
+                    no_of_people.div_ceil(MAX_PEOPLE_FOR_TABLE) + extra_table_days;
+                let mut rng = StdRng::seed_from_u64(seed);
+                let mut problem =
+                    generate_problem_with_rng(no_of_people, no_of_table_days, &mut rng);
+                if everybody_available {
+                    for person in problem.people.iter_mut() {
+                        person.available_dates = None;
+                    }
+                }
+                if without_relations {
+                    problem.people_relations.clear();
+                }
+                apply_variation(&mut problem, variation, &mut rng);
+                validate_problem(&problem).unwrap();
+                problem
+            },
+        )
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn solvers_return_valid_solutions(problem in problem_strategy(), seed in any::<u64>()) {
+        assert_solvers_return_valid_solutions(&problem, seed);
+    }
+
+    #[test]
+    fn solvers_fill_exactly_full_tables(no_of_table_days in 1..12usize, seed in any::<u64>()) {
+        let mut problem = generate_problem_with_rng(
+            no_of_table_days * MAX_PEOPLE_FOR_TABLE,
+            no_of_table_days,
+            &mut StdRng::seed_from_u64(seed),
+        );
+        for person in problem.people.iter_mut() {
+            person.available_dates = None;
+        }
+        assert_solvers_return_valid_solutions(&problem, seed);
+    }
+}
+
+#[test]
+fn solvers_leave_people_unassigned_when_no_seat_is_left() {
+    // Valid but infeasible, everybody can only come on the first date with one table
+    let mut problem =
+        generate_problem_with_rng(MAX_PEOPLE_FOR_TABLE + 1, 2, &mut StdRng::seed_from_u64(0));
+    let first_date = problem.tables.iter().map(|t| t.date).min().unwrap();
+    let second_date = first_date + Duration::days(1);
+    problem.tables[0].date = first_date;
+    problem.tables[1].date = second_date;
+    for person in problem.people.iter_mut() {
+        person.available_dates = Some([first_date.date_naive()].into());
+        person.unassigned_penalty = None;
+    }
+    validate_problem(&problem).unwrap();
+    for solver_name in SOLVER_NAMES {
+        let solve = solver_by_name(solver_name).unwrap();
+        let results = solve(
+            &problem,
+            chrono::Duration::milliseconds(20),
+            &mut StdRng::seed_from_u64(0),
+        );
+        assert_eq!(results.solution.unassigned.len(), 1, "{}", solver_name);
+        assert!(
+            matches!(
+                check_solution(&problem, &results.solution),
+                Err(SolutionError::MustBeSeated(_))
+            ),
+            "{}",
+            solver_name
+        );
+    }
+}